@@ -3,20 +3,17 @@ use std::path::Path;
 
 fn main() {
     let input_file_path = Path::new("../input");
-    let file_by_lines = input::read_lines(input_file_path, |line| Ok(line.to_string()))
-        .expect("could not read input file");
-
-    let bit_awway =
-        BitArray::from_lines(&file_by_lines).expect("Could not parse text lines to bit array.");
 
     // # Part 1
-    let commons = (0..bit_awway.width)
-        .map(|i| commonest_bit(&bit_awway.column(i), true))
-        .collect::<Vec<_>>();
+    // only the per-column counts of ones are needed, so stream the report line by line
+    let report_lines = input::file_by_line(input_file_path)
+        .expect("could not read input file")
+        .map(|line| line.expect("could not read line from input file"));
+    let column_counts = ColumnCounts::from_lines(report_lines)
+        .expect("Could not parse text lines to column counts.");
 
-    let base_10 = bits_to_usize(&commons);
-    let gamma_rate = base_10;
-    let epsilon_rate = !gamma_rate & bit_mask(bit_awway.width);
+    let gamma_rate = column_counts.gamma();
+    let epsilon_rate = column_counts.epsilon();
 
     println!("ɣ: {:#016b}, {}", gamma_rate, gamma_rate);
     println!("ε: {:#016b}, {}", epsilon_rate, epsilon_rate);
@@ -24,6 +21,12 @@ fn main() {
     assert_eq!(gamma_rate * epsilon_rate, 2261546);
 
     // # Part 2
+    let file_by_lines = input::read_lines(input_file_path, |line| Ok(line.to_string()))
+        .expect("could not read input file");
+
+    let bit_awway =
+        BitArray::from_lines(&file_by_lines).expect("Could not parse text lines to bit array.");
+
     // ## Oxygen Generator
     let oxygen_generator_bits =
         match (0..bit_awway.width).try_fold(bit_awway.clone(), |mut ba, pos| {
@@ -118,6 +121,72 @@ impl BitArray {
     }
 }
 
+/// Running count of ones in each bit column of a diagnostic report,
+/// accumulated one line at a time so the report need never be held in memory.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ColumnCounts {
+    ones: Vec<usize>,
+    length: usize,
+}
+
+impl ColumnCounts {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn from_lines<I, S>(lines: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        lines.into_iter().try_fold(Self::new(), |mut counts, line| {
+            counts.push(line.as_ref()).map(|_| counts)
+        })
+    }
+
+    /// Add a single line of bits to the running counts. The first line fixes
+    /// the width; any later line of a different width is rejected.
+    fn push(&mut self, line: &str) -> Result<(), String> {
+        let bits = line.chars().map(parse_bit).collect::<Result<Vec<_>, _>>()?;
+        if self.length == 0 {
+            self.ones = vec![0; bits.len()];
+        } else if bits.len() != self.width() {
+            return Err(format!(
+                "line {} has {} bits, expected {}",
+                self.length + 1,
+                bits.len(),
+                self.width()
+            ));
+        }
+        self.ones
+            .iter_mut()
+            .zip(bits)
+            .for_each(|(count, bit)| *count += bit as usize);
+        self.length += 1;
+        Ok(())
+    }
+
+    fn width(&self) -> usize {
+        self.ones.len()
+    }
+
+    /// Most common bit in each column (ties go to `1`), as with `commonest_bit`.
+    fn commonest_bits(&self) -> Vec<bool> {
+        self.ones
+            .iter()
+            .map(|&ones| 2 * ones >= self.length)
+            .collect()
+    }
+
+    fn gamma(&self) -> usize {
+        bits_to_usize(&self.commonest_bits())
+    }
+
+    fn epsilon(&self) -> usize {
+        !self.gamma() & bit_mask(self.width())
+    }
+}
+
 pub fn parse_bit(ch: char) -> Result<bool, String> {
     match ch {
         '0' => Ok(false),
@@ -179,9 +248,23 @@ mod tests {
         assert_eq!(gamma_rate, 22);
         assert_eq!(epsilon_rate, 9);
 
+        let column_counts = ColumnCounts::from_lines(sample_data.lines()).unwrap();
+        assert_eq!(column_counts.ones, vec![7, 5, 8, 7, 5]);
+        assert_eq!(column_counts.gamma(), gamma_rate);
+        assert_eq!(column_counts.epsilon(), epsilon_rate);
+
         // O2 Generator
 
     }
+
+    #[test]
+    fn column_counts_reject_ragged_lines() {
+        let result = ColumnCounts::from_lines(["00100", "1111", "10110"]);
+        assert_eq!(result, Err("line 2 has 4 bits, expected 5".to_string()));
+
+        let result = ColumnCounts::from_lines(["00100", "11x10"]);
+        assert_eq!(result, Err("could not parse x".to_string()));
+    }
 }
 
 #[allow(dead_code)]
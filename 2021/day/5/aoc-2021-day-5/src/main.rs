@@ -1,14 +1,14 @@
-use lazy_static::lazy_static;
-use regex::{self, Regex};
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::path::Path;
 use std::str::FromStr;
 
 fn main() {
     let input_file_path = Path::new("../input");
     let parsed_lines = input::read_lines(input_file_path, |line| {
-        Ok(LineVector::from_str(line).unwrap())
+        Ok(LineVector::from_str(line).unwrap_or_else(|err| panic!("{}: {:?}", err, line)))
     })
     .expect("could not read input file");
 
@@ -19,9 +19,10 @@ fn main() {
         .copied()
         .collect::<Vec<_>>();
 
-    let grid = SparseGrid::from_lines(&non_diagonals);
+    let grid = Grid::from_lines(&non_diagonals);
 
     println!("{}", grid.count_points_above(2));
+    assert_eq!(grid.count_points_above(2), count_overlaps(&non_diagonals));
 
     // Part 2
     let non_diagonals = parsed_lines
@@ -29,98 +30,554 @@ fn main() {
         .filter(|line| !line.is_diagonal() || line.is_equilateral())
         .copied()
         .collect::<Vec<_>>();
-    let grid = SparseGrid::from_lines(&non_diagonals);
+    let grid = Grid::from_lines(&non_diagonals);
 
     println!("{}", grid.count_points_above(2));
-}
+    assert_eq!(grid.count_points_above(2), count_overlaps(&non_diagonals));
+
+    // Hot-spots
+    println!("Overlap histogram: {:?}", grid.histogram());
+    if let Some((overlap, points)) = grid.max_overlap() {
+        println!("Maximum overlap of {} at {:?}", overlap, points);
+        for line in lines_through(&non_diagonals, points[0]) {
+            println!("  {:?} -> {:?}", line.start, line.end);
+        }
+    }
 
-fn parse_captures_to_i32(captures: &regex::Captures, i: usize) -> i32 {
-    captures.get(i).unwrap().as_str().parse::<i32>().unwrap()
+    // e.g. `cargo run -- vents.png` to draw the part 2 vents as an image
+    if let Some(image_path) = std::env::args().nth(1) {
+        let grid = SparseGrid::from_lines(&non_diagonals);
+        let mut file = BufWriter::new(File::create(&image_path).expect("could not create image"));
+        let (bounds, palette) = (grid.bounds(), Palette::default());
+        match Path::new(&image_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+        {
+            Some("png") => grid
+                .write_png(file, bounds, &palette)
+                .expect("could not write PNG"),
+            _ => grid
+                .write_ppm(&mut file, bounds, &palette)
+                .expect("could not write PPM"),
+        }
+    }
 }
 
+/// A straight line between two points in `N` dimensions (2 unless given).
 #[derive(Debug, PartialEq, Clone, Copy)]
-struct LineVector {
-    start: [i32; 2],
-    end: [i32; 2],
+struct LineVector<const N: usize = 2> {
+    start: [i32; N],
+    end: [i32; N],
 }
 
-impl LineVector {
+impl LineVector<2> {
+    #[allow(dead_code)]
     fn new(start: (i32, i32), end: (i32, i32)) -> Self {
         let start = [start.0, start.1];
         let end = [end.0, end.1];
         Self { start, end }
     }
+}
+
+impl<const N: usize> LineVector<N> {
+    #[allow(dead_code)]
+    fn from_points(start: [i32; N], end: [i32; N]) -> Self {
+        Self { start, end }
+    }
+
+    /// Whether the line moves along more than one axis.
     fn is_diagonal(&self) -> bool {
-        self.start[0] != self.end[0] && self.start[1] != self.end[1]
+        self.displacement().iter().filter(|d| **d != 0).count() > 1
     }
 
+    /// Whether the line moves the same distance along every axis.
     fn is_equilateral(&self) -> bool {
-        (self.end[0] - self.start[0]).abs() == (self.end[1] - self.start[1]).abs()
+        let d = self.displacement();
+        d.iter().all(|d_k| d_k.abs() == d[0].abs())
     }
 
-    fn gradient(&self) -> [i32; 2] {
-        (0..2)
-            .map(|k| match self.end[k].cmp(&self.start[k]) {
-                Ordering::Equal => 0,
-                Ordering::Greater => 1,
-                Ordering::Less => -1,
-            })
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap()
+    /// Displacement from `start` to `end`, widened so that lines between
+    /// any two `i32` points can be measured.
+    fn displacement(&self) -> [i64; N] {
+        sub(self.end, self.start)
+    }
+
+    /// Smallest whole-number step from `start` towards `end`, i.e. the
+    /// displacement divided by the gcd of its components.
+    fn gradient(&self) -> [i64; N] {
+        let d = self.displacement();
+        match d.iter().fold(0, |g, d_k| gcd(g, *d_k)) {
+            0 => [0; N],
+            g => d.map(|d_k| d_k / g),
+        }
     }
 
-    fn length(&self) -> i32 {
-        (self.end[0] - self.start[0])
-            .abs()
-            .max((self.end[1] - self.start[1]).abs())
-            + 1
+    /// Number of lattice points on the line, including both end points.
+    fn length(&self) -> i64 {
+        self.displacement().iter().fold(0, |g, d_k| gcd(g, *d_k)) + 1
     }
 
-    fn points(&self) -> Vec<[i32; 2]> {
+    fn point_at(&self, k: i64) -> [i32; N] {
         let grad = self.gradient();
-        (0..self.length())
-            .map(|k| [k * grad[0] + self.start[0], k * grad[1] + self.start[1]])
-            .collect()
+        std::array::from_fn(|i| (k * grad[i] + self.start[i] as i64) as i32)
+    }
+
+    /// Every integer lattice point lying exactly on the line.
+    fn points(&self) -> Vec<[i32; N]> {
+        (0..self.length()).map(|k| self.point_at(k)).collect()
+    }
+
+    /// Pixels drawn for the line by Bresenham's algorithm, generalised to
+    /// `N` axes: one per step along the longest axis, with each axis keeping
+    /// its own error term against that one and moving whenever the line has
+    /// passed half way to its next whole number (halves move away from
+    /// `start`), so lines of any slope are traced without gaps.
+    fn bresenham_points(&self) -> Vec<[i32; N]> {
+        let d = self.displacement();
+        let steps = d.iter().map(|d_k| d_k.abs()).max().unwrap_or(0);
+        // distance the line has run ahead of each axis's pixel, scaled by
+        // `2 · steps` and offset by half a pixel, so always in 0..2·steps
+        let mut error = [steps; N];
+        let mut point = self.start;
+        let mut points = Vec::with_capacity(steps as usize + 1);
+        points.push(point);
+        for _ in 0..steps {
+            for i in 0..N {
+                error[i] += 2 * d[i].abs();
+                if error[i] >= 2 * steps {
+                    error[i] -= 2 * steps;
+                    point[i] += d[i].signum() as i32;
+                }
+            }
+            points.push(point);
+        }
+        points
+    }
+
+    fn trace(&self, rasterisation: Rasterisation) -> Vec<[i32; N]> {
+        match rasterisation {
+            Rasterisation::LatticePoints => self.points(),
+            Rasterisation::Bresenham => self.bresenham_points(),
+        }
+    }
+
+    /// Whether `point` is one of the points traced by this line.
+    fn contains(&self, point: [i32; N]) -> bool {
+        let (d, n) = (self.gradient(), self.length());
+        let dp = sub(point, self.start);
+        match dot(d, d) {
+            0 => point == self.start,
+            d_2 => is_parallel(dp, d) && (0..n as i128).contains(&(dot(dp, d) / d_2)),
+        }
+    }
+
+    /// Every point shared by `self` and `other`, found by solving for the
+    /// crossing point (or the overlapping run, if the lines are collinear)
+    /// rather than by tracing both lines.
+    fn intersections(&self, other: &Self) -> Vec<[i32; N]> {
+        let (p, d, n) = (self.start, self.gradient(), self.length());
+        let (q, e, m) = (other.start, other.gradient(), other.length());
+        let pq = sub(q, p);
+
+        // a pair of axes in which the lines are not parallel, if any
+        let axes = (0..N)
+            .flat_map(|i| (i + 1..N).map(move |j| (i, j)))
+            .find(|&(i, j)| cross(d, e, i, j) != 0);
+
+        match axes {
+            None => {
+                // parallel: only share points if they lie on the same line
+                if !is_parallel(pq, d) {
+                    return vec![];
+                }
+                let d_2 = dot(d, d);
+                if d_2 == 0 {
+                    // `self` is a single point
+                    return match other.contains(p) {
+                        true => vec![p],
+                        false => vec![],
+                    };
+                }
+                // position of `other`'s end points along `self`, in steps of `d`
+                let t_0 = dot(pq, d) / d_2;
+                let t_1 = t_0 + (m - 1) as i128 * dot(e, d) / d_2;
+                let from = t_0.min(t_1).max(0) as i64;
+                let to = t_0.max(t_1).min(n as i128 - 1) as i64;
+                (from..=to).map(|k| self.point_at(k)).collect()
+            }
+            Some((i, j)) => {
+                // p + s·d == q + t·e, for whole numbers of steps s and t,
+                // solved in axes i and j then checked in all the others
+                let det = cross(d, e, i, j);
+                let s_num = cross(pq, e, i, j);
+                let t_num = cross(pq, d, i, j);
+                if s_num % det != 0 || t_num % det != 0 {
+                    return vec![];
+                }
+                let (s, t) = (s_num / det, t_num / det);
+                if !(0..n as i128).contains(&s) || !(0..m as i128).contains(&t) {
+                    return vec![];
+                }
+                let point = self.point_at(s as i64);
+                if point == other.point_at(t as i64) {
+                    vec![point]
+                } else {
+                    vec![]
+                }
+            }
+        }
     }
 }
 
-#[derive(Debug)]
-struct LineParseError;
+/// How a `LineVector` is turned into grid points.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Rasterisation {
+    /// Only the points the line passes through exactly.
+    LatticePoints,
+    /// A gap-free run of pixels approximating the line.
+    Bresenham,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// `a - b`, in `i64` so the difference of any two `i32` points fits.
+fn sub<const N: usize>(a: [i32; N], b: [i32; N]) -> [i64; N] {
+    std::array::from_fn(|i| a[i] as i64 - b[i] as i64)
+}
+
+/// Products of differences can exceed `i64`, so are taken in `i128`.
+fn dot<const N: usize>(a: [i64; N], b: [i64; N]) -> i128 {
+    a.iter()
+        .zip(b)
+        .map(|(a_i, b_i)| *a_i as i128 * b_i as i128)
+        .sum()
+}
+
+/// The 2×2 minor of the matrix with rows `a` and `b` in columns `i` and `j`.
+fn cross<const N: usize>(a: [i64; N], b: [i64; N], i: usize, j: usize) -> i128 {
+    a[i] as i128 * b[j] as i128 - a[j] as i128 * b[i] as i128
+}
+
+/// Whether `a` and `b` point along the same line, i.e. every 2×2 minor of
+/// the matrix with rows `a` and `b` is zero.
+fn is_parallel<const N: usize>(a: [i64; N], b: [i64; N]) -> bool {
+    (0..N).all(|i| (i + 1..N).all(|j| cross(a, b, i, j) == 0))
+}
+
+/// Count the points covered by at least two lines by intersecting each pair of
+/// lines, without rasterising every point of every line into a grid.
+fn count_overlaps<const N: usize>(lines: &[LineVector<N>]) -> usize {
+    let mut overlaps = HashSet::new();
+    for (i, a) in lines.iter().enumerate() {
+        for b in &lines[i + 1..] {
+            overlaps.extend(a.intersections(b));
+        }
+    }
+    overlaps.len()
+}
+
+/// One of the numbers making up a line, e.g. `x_0,y_0 -> x_1,y_1`, given by
+/// the end of the line and the axis.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Field {
+    Start(usize),
+    End(usize),
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (point, axis) = match self {
+            Field::Start(axis) => ("start", axis),
+            Field::End(axis) => ("end", axis),
+        };
+        match axis {
+            0..=2 => write!(f, "{} {}", point, ["x", "y", "z"][*axis]),
+            _ => write!(f, "{} axis {}", point, axis),
+        }
+    }
+}
+
+/// Why a line could not be parsed; columns count bytes from 1.
+#[derive(Debug, PartialEq)]
+enum LineParseError {
+    MissingArrow,
+    MissingComma {
+        before: Field,
+        column: usize,
+    },
+    InvalidCoordinate {
+        field: Field,
+        column: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for LineParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineParseError::MissingArrow => write!(f, "expected `->` between start and end points"),
+            LineParseError::MissingComma { before, column } => {
+                write!(
+                    f,
+                    "expected `,` before {} coordinate, in text from column {}",
+                    before, column
+                )
+            }
+            LineParseError::InvalidCoordinate {
+                field,
+                column,
+                text,
+            } => {
+                write!(
+                    f,
+                    "could not parse {} coordinate {:?} at column {}",
+                    field, text, column
+                )
+            }
+        }
+    }
+}
+
+/// Parse the point `x,y,...` in `text`, which starts at byte `offset` of the
+/// line, with `field` giving the end of the line for each axis.
+fn parse_point<const N: usize>(
+    text: &str,
+    offset: usize,
+    field: fn(usize) -> Field,
+) -> Result<[i32; N], LineParseError> {
+    let mut point = [0; N];
+    let (mut rest, mut offset) = (text, offset);
+    for (axis, coordinate) in point.iter_mut().enumerate() {
+        let number = match axis + 1 == N {
+            true => rest,
+            false => match rest.split_once(',') {
+                Some((number, _)) => number,
+                None => {
+                    return Err(LineParseError::MissingComma {
+                        before: field(axis + 1),
+                        column: offset + rest.len() - rest.trim_start().len() + 1,
+                    })
+                }
+            },
+        };
+        *coordinate = parse_coordinate(number, offset, field(axis))?;
+        rest = rest.get(number.len() + 1..).unwrap_or("");
+        offset += number.len() + 1;
+    }
+    Ok(point)
+}
 
-impl FromStr for LineVector {
+fn parse_coordinate(text: &str, offset: usize, field: Field) -> Result<i32, LineParseError> {
+    let number = text.trim();
+    let column = offset + text.len() - text.trim_start().len() + 1;
+    number
+        .parse::<i32>()
+        .map_err(|_| LineParseError::InvalidCoordinate {
+            field,
+            column,
+            text: number.to_string(),
+        })
+}
+
+impl<const N: usize> FromStr for LineVector<N> {
     type Err = LineParseError;
 
+    /// Parse `x_0,y_0 -> x_1,y_1` (with `N` coordinates per point), allowing
+    /// signed coordinates, any amount of whitespace between the parts, and a
+    /// trailing `\r`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        lazy_static! {
-            static ref PATTERN: Regex = Regex::new(r"^(\d+?),(\d+?) -> (\d+?),(\d+?)$").unwrap();
+        let (start, end) = s.split_once("->").ok_or(LineParseError::MissingArrow)?;
+        let start = parse_point(start, 0, Field::Start)?;
+        let end = parse_point(end, s.len() - end.len(), Field::End)?;
+        Ok(Self { start, end })
+    }
+}
+
+/// Largest bounding box, in cells, for which `Grid` allocates a dense array.
+const MAX_DENSE_CELLS: usize = 1 << 24;
+
+/// Smallest box `[min, max]` containing every end point of `lines`, which
+/// also contains every point traced along them.
+fn line_bounds(lines: &[LineVector]) -> Option<[(i32, i32); 2]> {
+    lines
+        .iter()
+        .flat_map(|line| [line.start, line.end])
+        .fold(None, |bounds, [x, y]| match bounds {
+            None => Some([(x, y), (x, y)]),
+            Some([(x_0, y_0), (x_1, y_1)]) => {
+                Some([(x_0.min(x), y_0.min(y)), (x_1.max(x), y_1.max(y))])
+            }
+        })
+}
+
+/// Vent counts over the lines' bounding box, stored in a dense array when the
+/// box is small enough and too few lines overlap to overflow its counts, and
+/// in a `SparseGrid` otherwise.
+#[derive(Debug)]
+enum Grid {
+    Dense(DenseGrid),
+    Sparse(SparseGrid),
+}
+
+impl Grid {
+    fn from_lines(lines: &[LineVector]) -> Self {
+        Self::from_traced_lines(lines, Rasterisation::LatticePoints)
+    }
+
+    fn from_traced_lines(lines: &[LineVector], rasterisation: Rasterisation) -> Self {
+        match line_bounds(lines) {
+            Some(bounds)
+                if lines.len() <= u16::MAX as usize
+                    && DenseGrid::cell_count(bounds).is_some_and(|n| n <= MAX_DENSE_CELLS) =>
+            {
+                Grid::Dense(DenseGrid::from_traced_lines(lines, bounds, rasterisation))
+            }
+            _ => Grid::Sparse(SparseGrid::from_traced_lines(lines, rasterisation)),
+        }
+    }
+
+    fn count_points_above(&self, x: i32) -> usize {
+        match self {
+            Grid::Dense(grid) => grid.count_points_above(x),
+            Grid::Sparse(grid) => grid.count_points_above(x),
         }
-        if let Some(captures) = PATTERN.captures(s) {
-            let start = [
-                parse_captures_to_i32(&captures, 1),
-                parse_captures_to_i32(&captures, 2),
-            ];
-            let end = [
-                parse_captures_to_i32(&captures, 3),
-                parse_captures_to_i32(&captures, 4),
-            ];
-            Ok(Self { start, end })
-        } else {
-            Err(LineParseError)
+    }
+
+    /// Every point covered by at least one line, with its count.
+    fn iter(&self) -> Box<dyn Iterator<Item = ([i32; 2], i32)> + '_> {
+        match self {
+            Grid::Dense(grid) => Box::new(grid.iter()),
+            Grid::Sparse(grid) => Box::new(grid.iter()),
         }
     }
+
+    /// Points with a count of at least `x`, in order of `x` then `y`.
+    fn points_above(&self, x: i32) -> Vec<[i32; 2]> {
+        let mut points = self
+            .iter()
+            .filter(|(_, v)| *v >= x)
+            .map(|(point, _)| point)
+            .collect::<Vec<_>>();
+        points.sort_unstable();
+        points
+    }
+
+    /// The highest count and every point with that count, in order of `x` then `y`.
+    fn max_overlap(&self) -> Option<(i32, Vec<[i32; 2]>)> {
+        let max = self.iter().map(|(_, v)| v).max()?;
+        Some((max, self.points_above(max)))
+    }
+
+    /// Number of points covered by exactly 1, 2, ... lines.
+    fn histogram(&self) -> BTreeMap<i32, usize> {
+        self.iter().fold(BTreeMap::new(), |mut histogram, (_, v)| {
+            *histogram.entry(v).or_insert(0) += 1;
+            histogram
+        })
+    }
+}
+
+/// Lines passing exactly through `point`.
+fn lines_through<const N: usize>(lines: &[LineVector<N>], point: [i32; N]) -> Vec<&LineVector<N>> {
+    lines.iter().filter(|line| line.contains(point)).collect()
 }
 
+/// Vent counts for every cell in a fixed bounding box, stored row by row.
 #[derive(Debug)]
-struct SparseGrid {
-    points: HashMap<(i32, i32), i32>,
+struct DenseGrid {
+    bounds: [(i32, i32); 2],
+    width: usize,
+    counts: Vec<u16>,
 }
 
-impl SparseGrid {
-    fn from_lines(lines: &[LineVector]) -> Self {
+impl DenseGrid {
+    /// Number of cells within `bounds`, or `None` if too many to count.
+    fn cell_count(bounds: [(i32, i32); 2]) -> Option<usize> {
+        let [(x_0, y_0), (x_1, y_1)] = bounds;
+        let width = x_1.abs_diff(x_0) as u64 + 1;
+        let height = y_1.abs_diff(y_0) as u64 + 1;
+        usize::try_from(width.checked_mul(height)?).ok()
+    }
+
+    fn with_bounds(bounds: [(i32, i32); 2]) -> Self {
+        let width = bounds[1].0.abs_diff(bounds[0].0) as usize + 1;
+        let cells = Self::cell_count(bounds).expect("too many cells for a dense grid");
+        let counts = vec![0; cells];
+        Self {
+            bounds,
+            width,
+            counts,
+        }
+    }
+
+    fn from_traced_lines(
+        lines: &[LineVector],
+        bounds: [(i32, i32); 2],
+        rasterisation: Rasterisation,
+    ) -> Self {
+        let mut grid = Self::with_bounds(bounds);
+        for line in lines {
+            for point in line.trace(rasterisation) {
+                let i = grid
+                    .index(point)
+                    .expect("line point outside of the grid's bounds");
+                grid.counts[i] += 1;
+            }
+        }
+        grid
+    }
+
+    fn index(&self, [x, y]: [i32; 2]) -> Option<usize> {
+        let [(x_0, y_0), (x_1, y_1)] = self.bounds;
+        match (x_0..=x_1).contains(&x) && (y_0..=y_1).contains(&y) {
+            true => Some(y.abs_diff(y_0) as usize * self.width + x.abs_diff(x_0) as usize),
+            false => None,
+        }
+    }
+
+    /// Points covered by at least one line with a count of at least `x`;
+    /// cells no line passes through are not points, whatever `x` is.
+    fn count_points_above(&self, x: i32) -> usize {
+        self.counts
+            .iter()
+            .filter(|v| **v > 0 && **v as i32 >= x)
+            .count()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = ([i32; 2], i32)> + '_ {
+        let (x_0, y_0) = self.bounds[0];
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v > 0)
+            .map(move |(i, v)| {
+                let (x, y) = ((i % self.width) as i32, (i / self.width) as i32);
+                ([x_0 + x, y_0 + y], *v as i32)
+            })
+    }
+}
+
+#[derive(Debug)]
+struct SparseGrid<const N: usize = 2> {
+    points: HashMap<[i32; N], i32>,
+}
+
+impl<const N: usize> SparseGrid<N> {
+    fn from_lines(lines: &[LineVector<N>]) -> Self {
+        Self::from_traced_lines(lines, Rasterisation::LatticePoints)
+    }
+
+    fn from_traced_lines(lines: &[LineVector<N>], rasterisation: Rasterisation) -> Self {
         let points = lines.iter().fold(HashMap::new(), |mut map, line| {
-            line.points().iter().for_each(| &[x, y] | {
-                *map.entry((x, y)).or_insert(0) += 1;
+            line.trace(rasterisation).into_iter().for_each(|point| {
+                *map.entry(point).or_insert(0) += 1;
             });
             map
         });
@@ -131,46 +588,143 @@ impl SparseGrid {
         self.points.values().filter(|v| **v >= x).count()
     }
 
+    fn iter(&self) -> impl Iterator<Item = ([i32; N], i32)> + '_ {
+        self.points.iter().map(|(&point, &v)| (point, v))
+    }
+}
+
+impl SparseGrid<2> {
     fn bounds(&self) -> [(i32, i32); 2] {
         let min_bound = (
-            self.points.keys().map(|(x, _)| *x).min().unwrap(),
-            self.points.keys().map(|(_, y)| *y).min().unwrap(),
+            self.points.keys().map(|[x, _]| *x).min().unwrap(),
+            self.points.keys().map(|[_, y]| *y).min().unwrap(),
         );
         let max_bound = (
-            self.points.keys().map(|(x, _)| *x).max().unwrap(),
-            self.points.keys().map(|(_, y)| *y).max().unwrap(),
+            self.points.keys().map(|[x, _]| *x).max().unwrap(),
+            self.points.keys().map(|[_, y]| *y).max().unwrap(),
         );
         [min_bound, max_bound]
     }
 
+    /// Counts over the whole grid as rows of `y`, each holding columns of `x`,
+    /// starting from the grid's minimum bound rather than from the origin.
+    #[allow(dead_code)]
     fn to_dense(&self) -> Vec<Vec<i32>> {
-        let bounds = self.bounds();
-        let mut dense_grid: Vec<Vec<i32>> = (bounds[0].0..=bounds[1].1)
-            .map(|_| (bounds[0].0..=bounds[1].0).map(|_| 0).collect::<Vec<_>>())
-            .collect();
+        self.viewport(self.bounds())
+    }
 
-        self.points
-            .iter()
-            .for_each(|((j, i), v)| dense_grid[*i as usize][*j as usize] = *v);
-        dense_grid
-    }
-
-    fn plot(&self) -> () {
-        let grid = self.to_dense();
-        for row in grid.iter() {
-            for e in row {
-                match e {
-                    0 => print!("█"),
-                    1..=9 => print!("{}", e),
-                    _ => print!("?"),
-                }
-            }
-            println!()
+    /// Counts in the box `[min, max]` (inclusive), as rows of `y`, each holding
+    /// columns of `x`. Points outside the grid's bounds are zero.
+    fn viewport(&self, viewport: [(i32, i32); 2]) -> Vec<Vec<i32>> {
+        let [(x_0, y_0), (x_1, y_1)] = viewport;
+        (y_0..=y_1)
+            .map(|y| {
+                (x_0..=x_1)
+                    .map(|x| self.points.get(&[x, y]).copied().unwrap_or(0))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[allow(dead_code)]
+    fn plot(&self) {
+        self.plot_viewport(self.bounds(), &Palette::default())
+    }
+
+    fn plot_viewport(&self, viewport: [(i32, i32); 2], palette: &Palette) {
+        for row in self.viewport(viewport) {
+            println!(
+                "{}",
+                row.iter().map(|&e| palette.glyph(e)).collect::<String>()
+            );
         }
         println!()
     }
+
+    /// Red, green and blue bytes for each point of `viewport`, row by row.
+    fn to_rgb(&self, viewport: [(i32, i32); 2], palette: &Palette) -> Vec<u8> {
+        self.viewport(viewport)
+            .iter()
+            .flatten()
+            .flat_map(|&e| palette.colour(e))
+            .collect()
+    }
+
+    fn write_ppm<W: Write>(
+        &self,
+        writer: &mut W,
+        viewport: [(i32, i32); 2],
+        palette: &Palette,
+    ) -> io::Result<()> {
+        let [(x_0, y_0), (x_1, y_1)] = viewport;
+        write!(writer, "P6\n{} {}\n255\n", x_1 - x_0 + 1, y_1 - y_0 + 1)?;
+        writer.write_all(&self.to_rgb(viewport, palette))
+    }
+
+    fn write_png<W: Write>(
+        &self,
+        writer: W,
+        viewport: [(i32, i32); 2],
+        palette: &Palette,
+    ) -> Result<(), png::EncodingError> {
+        let [(x_0, y_0), (x_1, y_1)] = viewport;
+        let mut encoder = png::Encoder::new(writer, (x_1 - x_0 + 1) as u32, (y_1 - y_0 + 1) as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()?
+            .write_image_data(&self.to_rgb(viewport, palette))
+    }
+}
+
+/// Characters and colours used to draw each vent count; counts beyond the end
+/// of either list use its last entry.
+#[derive(Debug, Clone)]
+struct Palette {
+    glyphs: Vec<char>,
+    colours: Vec<[u8; 3]>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        let glyphs = "█123456789abcdefghijklmnopqrstuvwxyz+".chars().collect();
+        let colours = vec![
+            [0, 0, 0],
+            [64, 0, 96],
+            [160, 0, 96],
+            [224, 64, 32],
+            [255, 160, 0],
+            [255, 255, 128],
+        ];
+        Self { glyphs, colours }
+    }
+}
+
+impl Palette {
+    /// A palette from at least one glyph and one colour, or `None` if
+    /// either list is empty.
+    #[allow(dead_code)]
+    fn new(glyphs: &str, colours: &[[u8; 3]]) -> Option<Self> {
+        let glyphs = glyphs.chars().collect::<Vec<_>>();
+        match glyphs.is_empty() || colours.is_empty() {
+            true => None,
+            false => Some(Self {
+                glyphs,
+                colours: colours.to_vec(),
+            }),
+        }
+    }
+
+    fn glyph(&self, count: i32) -> char {
+        self.glyphs[(count.max(0) as usize).min(self.glyphs.len() - 1)]
+    }
+
+    fn colour(&self, count: i32) -> [u8; 3] {
+        self.colours[(count.max(0) as usize).min(self.colours.len() - 1)]
+    }
 }
 
+#[cfg(test)]
 mod tests {
     use crate::*;
     use std::str::FromStr;
@@ -196,6 +750,57 @@ mod tests {
         assert_eq!(parsed.last().unwrap().end, [98, 844]);
     }
 
+    #[test]
+    fn parses_signed_and_spaced_strings() {
+        let expected = LineVector::new((-5, 190), (756, -381));
+        for sample in [
+            "-5,190 -> 756,-381",
+            "-5,190->756,-381",
+            "  -5 , 190   ->\t756,  -381 ",
+            "-5,190 -> 756,-381\r",
+            "-5,+190 -> 756,-381",
+        ] {
+            assert_eq!(LineVector::from_str(sample), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn parse_errors_locate_field() {
+        assert_eq!(
+            LineVector::<2>::from_str("565,190 => 756,381"),
+            Err(LineParseError::MissingArrow)
+        );
+        assert_eq!(
+            LineVector::<2>::from_str("565,190 -> 756 381"),
+            Err(LineParseError::MissingComma {
+                before: Field::End(1),
+                column: 12
+            })
+        );
+        assert_eq!(
+            LineVector::<2>::from_str("565,1x0 -> 756,381"),
+            Err(LineParseError::InvalidCoordinate {
+                field: Field::Start(1),
+                column: 5,
+                text: "1x0".to_string()
+            })
+        );
+
+        let error = LineVector::<2>::from_str("565,190 -> , 381").unwrap_err();
+        assert_eq!(
+            error,
+            LineParseError::InvalidCoordinate {
+                field: Field::End(0),
+                column: 12,
+                text: "".to_string()
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "could not parse end x coordinate \"\" at column 12"
+        );
+    }
+
     #[test]
     fn recognised_diagonal_line() {
         let line = LineVector::new((973, 82), (308, 747));
@@ -221,6 +826,86 @@ mod tests {
         assert_eq!(vec![[3, 3], [4, 4], [5, 5]], points);
     }
 
+    #[test]
+    fn arbitrary_slope_gradients() {
+        assert_eq!([2, 1], LineVector::new((0, 0), (6, 3)).gradient());
+        assert_eq!([-1, 3], LineVector::new((4, 0), (2, 6)).gradient());
+        assert_eq!([5, -7], LineVector::new((0, 7), (5, 0)).gradient());
+        assert_eq!([0, 0], LineVector::new((1, 1), (1, 1)).gradient());
+    }
+
+    #[test]
+    fn trace_arbitrary_slope_points() {
+        let line = LineVector::new((0, 0), (6, 3));
+        assert_eq!(line.length(), 4);
+        assert_eq!(vec![[0, 0], [2, 1], [4, 2], [6, 3]], line.points());
+
+        let line = LineVector::new((4, 0), (2, 6));
+        assert_eq!(vec![[4, 0], [3, 3], [2, 6]], line.points());
+
+        // no lattice points between the end points
+        let line = LineVector::new((0, 7), (5, 0));
+        assert_eq!(vec![[0, 7], [5, 0]], line.points());
+    }
+
+    #[test]
+    fn bresenham_rasterisation() {
+        let line = LineVector::new((0, 0), (6, 3));
+        assert_eq!(
+            vec![[0, 0], [1, 1], [2, 1], [3, 2], [4, 2], [5, 3], [6, 3]],
+            line.bresenham_points()
+        );
+
+        let line = LineVector::new((2, 6), (4, 0));
+        let points = line.bresenham_points();
+        assert_eq!(points.len(), 7);
+        assert_eq!(points.first(), Some(&[2, 6]));
+        assert_eq!(points.last(), Some(&[4, 0]));
+
+        // long lines step without their error terms overflowing
+        let line = LineVector::new((0, 0), (50_000, 30_000));
+        let points = line.bresenham_points();
+        assert_eq!(points.len(), 50_001);
+        assert_eq!(points.last(), Some(&[50_000, 30_000]));
+        for (k, [x, y]) in points.iter().enumerate() {
+            assert_eq!(*x as usize, k);
+            // within half a pixel of the true line, halves rounded up
+            let twice_offset = 2 * (*y as i64 * 50_000 - *x as i64 * 30_000);
+            assert!(-50_000 < twice_offset && twice_offset <= 50_000);
+        }
+        let line = LineVector::<3>::from_points([i32::MIN, 0, 5], [i32::MIN + 70_000, -3, 5]);
+        let points = line.bresenham_points();
+        assert_eq!(points.len(), 70_001);
+        assert_eq!(points[35_000], [i32::MIN + 35_000, -2, 5]);
+        assert_eq!(points.last(), Some(&[i32::MIN + 70_000, -3, 5]));
+
+        // matches the lattice points for horizontal, vertical and 45° lines
+        for line in [
+            LineVector::new((0, 9), (5, 9)),
+            LineVector::new((7, 0), (7, 4)),
+            LineVector::new((8, 0), (0, 8)),
+        ] {
+            assert_eq!(line.points(), line.bresenham_points());
+        }
+    }
+
+    #[test]
+    fn arbitrary_slope_intersections() {
+        let a = LineVector::new((0, 0), (6, 3));
+        let b = LineVector::new((0, 2), (6, 2));
+        let c = LineVector::new((0, 3), (6, 0));
+        assert_eq!(a.intersections(&b), vec![[4, 2]]);
+        // lines cross at (3, 1.5)
+        assert!(a.intersections(&c).is_empty());
+        assert!(a.contains([2, 1]));
+        assert!(!a.contains([1, 0]));
+
+        let grid = SparseGrid::from_lines(&[a, b, c]);
+        assert_eq!(grid.count_points_above(2), count_overlaps(&[a, b, c]));
+        let grid = SparseGrid::from_traced_lines(&[a, b, c], Rasterisation::Bresenham);
+        assert_eq!(grid.count_points_above(2), 4);
+    }
+
     #[test]
     fn example_part_1() {
         let lines = vec![
@@ -274,6 +959,338 @@ mod tests {
         assert_eq!(grid.count_points_above(2), 12);
         grid.plot();
     }
+
+    #[test]
+    fn dense_and_sparse_grids_agree() {
+        let lines = vec![
+            LineVector::new((0, 9), (5, 9)),
+            LineVector::new((8, 0), (0, 8)),
+            LineVector::new((9, 4), (3, 4)),
+            LineVector::new((2, 2), (2, 1)),
+            LineVector::new((7, 0), (7, 4)),
+            LineVector::new((6, 4), (2, 0)),
+            LineVector::new((0, 9), (2, 9)),
+            LineVector::new((3, 4), (1, 4)),
+            LineVector::new((0, 0), (8, 8)),
+            LineVector::new((5, 5), (8, 2)),
+        ];
+        let grid = Grid::from_lines(&lines);
+        assert!(matches!(grid, Grid::Dense(_)));
+        assert_eq!(grid.count_points_above(2), 12);
+        let sparse = SparseGrid::from_lines(&lines);
+        for x in [-1, 0, 1, 2] {
+            assert_eq!(grid.count_points_above(x), sparse.count_points_above(x));
+        }
+
+        // offset from the origin, including negative coordinates
+        let shifted = lines
+            .iter()
+            .map(|line| LineVector {
+                start: [line.start[0] - 20, line.start[1] + 7],
+                end: [line.end[0] - 20, line.end[1] + 7],
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(line_bounds(&shifted), Some([(-20, 7), (-11, 16)]));
+        assert_eq!(Grid::from_lines(&shifted).count_points_above(2), 12);
+
+        // too big to allocate densely
+        let far_apart = vec![
+            LineVector::new((0, 0), (0, 3)),
+            LineVector::new((0, 2), (0, 5)),
+            LineVector::new((100_000, 100_000), (100_000, 100_001)),
+        ];
+        let grid = Grid::from_lines(&far_apart);
+        assert!(matches!(grid, Grid::Sparse(_)));
+        assert_eq!(grid.count_points_above(2), 2);
+
+        // spanning the whole coordinate range, too wide to measure in `i32`
+        let extremes = vec![
+            LineVector::new((-2_000_000_000, 0), (-2_000_000_000, 3)),
+            LineVector::new((-2_000_000_000, 2), (-2_000_000_000, 5)),
+            LineVector::new((2_000_000_000, i32::MIN), (2_000_000_000, i32::MIN + 1)),
+            LineVector::new((i32::MAX, i32::MAX), (i32::MAX, i32::MAX)),
+        ];
+        let grid = Grid::from_lines(&extremes);
+        assert!(matches!(grid, Grid::Sparse(_)));
+        assert_eq!(grid.count_points_above(2), 2);
+        assert_eq!(grid.count_points_above(2), count_overlaps(&extremes));
+
+        // near the ends of the range but small enough to be dense
+        let corner = vec![
+            LineVector::new((i32::MAX - 4, i32::MIN), (i32::MAX, i32::MIN + 4)),
+            LineVector::new((i32::MAX, i32::MIN), (i32::MAX - 4, i32::MIN + 4)),
+            LineVector::new((i32::MAX - 4, i32::MIN + 2), (i32::MAX, i32::MIN + 2)),
+        ];
+        let grid = Grid::from_lines(&corner);
+        assert!(matches!(grid, Grid::Dense(_)));
+        assert_eq!(grid.count_points_above(2), 1);
+        assert_eq!(
+            grid.iter().collect::<HashMap<_, _>>(),
+            SparseGrid::from_lines(&corner).points
+        );
+
+        // more lines through one point than a dense count can hold
+        let stacked = vec![LineVector::new((0, 0), (0, 1)); u16::MAX as usize + 5];
+        let grid = Grid::from_lines(&stacked);
+        assert!(matches!(grid, Grid::Sparse(_)));
+        assert_eq!(grid.count_points_above(65_540), 2);
+        assert_eq!(grid.max_overlap(), Some((65_540, vec![[0, 0], [0, 1]])));
+    }
+
+    #[test]
+    fn densify_offset_grid() {
+        let lines = vec![
+            LineVector::new((-2, 5), (1, 5)),
+            LineVector::new((0, 4), (0, 7)),
+        ];
+        let grid = SparseGrid::from_lines(&lines);
+        assert_eq!(grid.bounds(), [(-2, 4), (1, 7)]);
+        assert_eq!(
+            grid.to_dense(),
+            vec![
+                vec![0, 0, 1, 0],
+                vec![1, 1, 2, 1],
+                vec![0, 0, 1, 0],
+                vec![0, 0, 1, 0],
+            ]
+        );
+        assert_eq!(
+            grid.viewport([(-1, 6), (2, 8)]),
+            vec![vec![0, 1, 0, 0], vec![0, 1, 0, 0], vec![0, 0, 0, 0]]
+        );
+    }
+
+    #[test]
+    fn palette_glyphs_and_colours() {
+        let palette = Palette::default();
+        assert_eq!(palette.glyph(0), '█');
+        assert_eq!(palette.glyph(9), '9');
+        assert_eq!(palette.glyph(10), 'a');
+        assert_eq!(palette.glyph(1000), '+');
+
+        let palette = Palette::new(".#", &[[255, 255, 255], [255, 0, 0]]).unwrap();
+        assert_eq!(palette.glyph(0), '.');
+        assert_eq!(palette.glyph(3), '#');
+        assert_eq!(palette.colour(3), [255, 0, 0]);
+
+        assert!(Palette::new("", &[[0, 0, 0]]).is_none());
+        assert!(Palette::new(".#", &[]).is_none());
+    }
+
+    #[test]
+    fn export_images() {
+        let lines = vec![
+            LineVector::new((0, 0), (1, 1)),
+            LineVector::new((1, 0), (1, 1)),
+        ];
+        let grid = SparseGrid::from_lines(&lines);
+        let palette = Palette::new(".#", &[[0, 0, 0], [0, 0, 255], [255, 0, 0]]).unwrap();
+
+        let mut ppm = Vec::new();
+        grid.write_ppm(&mut ppm, grid.bounds(), &palette).unwrap();
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend([0, 0, 255, 0, 0, 255, 0, 0, 0, 255, 0, 0]);
+        assert_eq!(ppm, expected);
+
+        let mut png = Vec::new();
+        grid.write_png(&mut png, [(0, 0), (3, 2)], &palette)
+            .unwrap();
+        let decoder = png::Decoder::new(png.as_slice());
+        let reader = decoder.read_info().unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (4, 3));
+    }
+
+    #[test]
+    fn three_dimensional_lines() {
+        let line = LineVector::<3>::from_str("0,0,0 -> 4,-2,6").unwrap();
+        assert_eq!(line, LineVector::from_points([0, 0, 0], [4, -2, 6]));
+        assert!(line.is_diagonal());
+        assert!(!line.is_equilateral());
+        assert_eq!(line.gradient(), [2, -1, 3]);
+        assert_eq!(line.points(), vec![[0, 0, 0], [2, -1, 3], [4, -2, 6]]);
+        assert_eq!(line.bresenham_points().len(), 7);
+        assert!(line.contains([2, -1, 3]));
+        assert!(!line.contains([2, -1, 4]));
+
+        assert_eq!(
+            LineVector::<3>::from_str("0,0,0 -> 4,-2"),
+            Err(LineParseError::MissingComma {
+                before: Field::End(2),
+                column: 12
+            })
+        );
+
+        let lines = vec![
+            LineVector::from_points([0, 0, 0], [4, 4, 4]),
+            LineVector::from_points([2, 2, 0], [2, 2, 9]),
+            LineVector::from_points([0, 4, 0], [4, 0, 4]),
+            LineVector::from_points([3, 3, 3], [6, 6, 6]),
+            // skew: passes over the first line without meeting it
+            LineVector::from_points([0, 1, 0], [4, 1, 0]),
+        ];
+        assert_eq!(lines[0].intersections(&lines[1]), vec![[2, 2, 2]]);
+        assert_eq!(lines[0].intersections(&lines[2]), vec![[2, 2, 2]]);
+        assert_eq!(
+            lines[0].intersections(&lines[3]),
+            vec![[3, 3, 3], [4, 4, 4]]
+        );
+        assert!(lines[0].intersections(&lines[4]).is_empty());
+        assert!(lines[1].intersections(&lines[4]).is_empty());
+
+        let grid = SparseGrid::from_lines(&lines);
+        assert_eq!(grid.count_points_above(2), 3);
+        assert_eq!(grid.count_points_above(3), 1);
+        assert_eq!(count_overlaps(&lines), 3);
+        assert_eq!(lines_through(&lines, [2, 2, 2]).len(), 3);
+    }
+
+    #[test]
+    fn hot_spot_queries() {
+        let lines = vec![
+            LineVector::new((0, 9), (5, 9)),
+            LineVector::new((8, 0), (0, 8)),
+            LineVector::new((9, 4), (3, 4)),
+            LineVector::new((2, 2), (2, 1)),
+            LineVector::new((7, 0), (7, 4)),
+            LineVector::new((6, 4), (2, 0)),
+            LineVector::new((0, 9), (2, 9)),
+            LineVector::new((3, 4), (1, 4)),
+            LineVector::new((0, 0), (8, 8)),
+            LineVector::new((5, 5), (8, 2)),
+        ];
+        let sparse = Grid::Sparse(SparseGrid::from_lines(&lines));
+        for grid in [Grid::from_lines(&lines), sparse] {
+            assert_eq!(grid.points_above(3), vec![[4, 4], [6, 4]]);
+            assert_eq!(grid.max_overlap(), Some((3, vec![[4, 4], [6, 4]])));
+            assert_eq!(grid.histogram(), BTreeMap::from([(1, 27), (2, 10), (3, 2)]));
+            assert_eq!(grid.points_above(2).len(), grid.count_points_above(2));
+        }
+
+        assert_eq!(
+            lines_through(&lines, [4, 4]),
+            vec![&lines[1], &lines[2], &lines[8]]
+        );
+        assert!(lines_through(&lines, [9, 9]).is_empty());
+    }
+
+    #[test]
+    fn intersecting_lines() {
+        let horizontal = LineVector::new((0, 4), (6, 4));
+        let vertical = LineVector::new((3, 0), (3, 9));
+        let diagonal = LineVector::new((0, 0), (8, 8));
+        let anti_diagonal = LineVector::new((0, 3), (3, 0));
+        let anti_diagonal_odd = LineVector::new((0, 4), (4, 0));
+
+        assert_eq!(horizontal.intersections(&vertical), vec![[3, 4]]);
+        assert_eq!(horizontal.intersections(&diagonal), vec![[4, 4]]);
+        assert_eq!(vertical.intersections(&diagonal), vec![[3, 3]]);
+        // crossing between lattice points
+        assert!(diagonal.intersections(&anti_diagonal).is_empty());
+        assert_eq!(diagonal.intersections(&anti_diagonal_odd), vec![[2, 2]]);
+        // would cross if extended
+        assert!(vertical
+            .intersections(&LineVector::new((4, 0), (9, 0)))
+            .is_empty());
+    }
+
+    #[test]
+    fn lines_spanning_the_coordinate_range() {
+        let west = LineVector::new((-2_000_000_000, 0), (-1_999_999_990, 10));
+        let east = LineVector::new((2_000_000_000, 0), (2_000_000_000, 10));
+        assert!(west.intersections(&east).is_empty());
+        assert_eq!(count_overlaps(&[west, east]), 0);
+
+        let horizontal = LineVector::new((i32::MIN, 0), (i32::MAX, 0));
+        let vertical = LineVector::new((5, i32::MAX), (5, i32::MIN));
+        assert_eq!(horizontal.intersections(&vertical), vec![[5, 0]]);
+        assert!(horizontal.contains([i32::MAX, 0]));
+        assert_eq!(horizontal.length(), 1 << 32);
+
+        let a = LineVector::new((i32::MIN, 7), (0, 7));
+        let b = LineVector::new((-3, 7), (i32::MAX, 7));
+        assert_eq!(a.intersections(&b), vec![[-3, 7], [-2, 7], [-1, 7], [0, 7]]);
+        assert_eq!(count_overlaps(&[a, b, horizontal, vertical]), 6);
+    }
+
+    #[test]
+    fn overlapping_collinear_lines() {
+        let a = LineVector::new((0, 9), (5, 9));
+        let b = LineVector::new((7, 9), (2, 9));
+        let c = LineVector::new((6, 9), (9, 9));
+        assert_eq!(a.intersections(&b), vec![[2, 9], [3, 9], [4, 9], [5, 9]]);
+        assert!(a.intersections(&c).is_empty());
+        assert_eq!(b.intersections(&c), vec![[7, 9], [6, 9]]);
+
+        let d = LineVector::new((6, 4), (2, 0));
+        let e = LineVector::new((1, -1), (3, 1));
+        assert_eq!(d.intersections(&e), vec![[3, 1], [2, 0]]);
+
+        let point = LineVector::new((4, 9), (4, 9));
+        assert_eq!(point.intersections(&a), vec![[4, 9]]);
+        assert_eq!(a.intersections(&point), vec![[4, 9]]);
+        assert!(point.intersections(&c).is_empty());
+    }
+
+    #[test]
+    fn analytic_count_matches_grid() {
+        let lines = vec![
+            LineVector::new((0, 9), (5, 9)),
+            LineVector::new((8, 0), (0, 8)),
+            LineVector::new((9, 4), (3, 4)),
+            LineVector::new((2, 2), (2, 1)),
+            LineVector::new((7, 0), (7, 4)),
+            LineVector::new((6, 4), (2, 0)),
+            LineVector::new((0, 9), (2, 9)),
+            LineVector::new((3, 4), (1, 4)),
+            LineVector::new((0, 0), (8, 8)),
+            LineVector::new((5, 5), (8, 2)),
+        ];
+        let non_diagonals = lines
+            .iter()
+            .filter(|line| !line.is_diagonal())
+            .copied()
+            .collect::<Vec<_>>();
+
+        assert_eq!(count_overlaps(&non_diagonals), 5);
+        assert_eq!(count_overlaps(&lines), 12);
+    }
+
+    // cargo test --release -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn benchmark_overlap_counting() {
+        use std::path::Path;
+        use std::time::Instant;
+
+        let lines = input::read_lines(Path::new("../input"), |line| {
+            Ok(LineVector::from_str(line).unwrap())
+        })
+        .unwrap();
+
+        let timer = Instant::now();
+        let from_grid = SparseGrid::from_lines(&lines).count_points_above(2);
+        let grid_time = timer.elapsed();
+
+        let timer = Instant::now();
+        let from_dense_grid = Grid::from_lines(&lines).count_points_above(2);
+        let dense_grid_time = timer.elapsed();
+
+        let timer = Instant::now();
+        let from_intersections = count_overlaps(&lines);
+        let intersections_time = timer.elapsed();
+
+        println!("grid:          {} in {:?}", from_grid, grid_time);
+        println!(
+            "dense grid:    {} in {:?}",
+            from_dense_grid, dense_grid_time
+        );
+        println!(
+            "intersections: {} in {:?}",
+            from_intersections, intersections_time
+        );
+        assert_eq!(from_grid, from_intersections);
+        assert_eq!(from_grid, from_dense_grid);
+    }
 }
 
 #[allow(dead_code)]
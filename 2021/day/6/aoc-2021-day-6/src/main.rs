@@ -1,5 +1,10 @@
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
 use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::num::ParseIntError;
+use std::ops::{Add, Mul};
 use std::path::Path;
 use std::str::FromStr;
 
@@ -12,14 +17,97 @@ fn main() {
         .to_string();
 
     let initial_state = School::from_str(&input).expect("could not parse input data");
-    println!("After 80 days: {}", initial_state.clone().step_by(80).count());
-    println!("After 256 days: {}", initial_state.clone().step_by(256).count());
+    println!(
+        "After 80 days: {}",
+        initial_state.clone().step_by(80).count()
+    );
+    println!(
+        "After 256 days: {}",
+        initial_state.clone().step_by(256).count()
+    );
+    assert_eq!(
+        initial_state.project::<u64>(256),
+        initial_state.clone().step_by(256).count()
+    );
 
+    let after_a_thousand_days = initial_state.project::<BigUint>(1000);
+    println!("After 1000 days: {}", after_a_thousand_days);
+    println!(
+        "After 10¹⁸ days: {} (mod 1e9 + 7)",
+        initial_state.project::<Modular<1_000_000_007>>(1_000_000_000_000_000_000)
+    );
+    println!(
+        "Growth rate: ×{:.6} per day",
+        initial_state.model.growth_rate()
+    );
+
+    // e.g. `cargo run -- history.csv` to save the first 256 days for plotting
+    if let Some(csv_path) = std::env::args().nth(1) {
+        let mut file = BufWriter::new(File::create(csv_path).expect("could not create CSV file"));
+        initial_state
+            .write_history_csv(&mut file, 256)
+            .expect("could not write CSV file");
+    }
+}
+
+/// Rules for how lanternfish reproduce: a fish spawns a new fish every
+/// `cycle` days, and a newborn fish takes an extra `maturation` days before
+/// its first cycle begins.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Model {
+    cycle: usize,
+    maturation: usize,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self::new(7, 2)
+    }
+}
+
+impl Model {
+    fn new(cycle: usize, maturation: usize) -> Self {
+        assert!(cycle > 0, "reproduction cycle must be at least one day");
+        Self { cycle, maturation }
+    }
+
+    /// Number of distinct timer values, from 0 up to a newborn's timer.
+    fn buckets(&self) -> usize {
+        self.cycle + self.maturation
+    }
+
+    /// Factor by which the school eventually grows each day: the dominant
+    /// eigenvalue of the transition matrix, i.e. the positive root of its
+    /// characteristic polynomial `λ^(cycle + maturation) = λ^maturation + 1`.
+    fn growth_rate(&self) -> f64 {
+        let f = |x: f64| x.powi(self.buckets() as i32) - x.powi(self.maturation as i32) - 1.0;
+        // f(1) < 0 <= f(2), and f has exactly one positive root
+        let (mut low, mut high) = (1.0, 2.0);
+        while high - low > f64::EPSILON {
+            let mid = (low + high) / 2.0;
+            if f(mid) < 0.0 {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// Timer of a fish after one day, with the timer of its offspring if it
+    /// spawned one.
+    fn tick(&self, timer: usize) -> (usize, Option<usize>) {
+        match timer {
+            0 => (self.cycle - 1, Some(self.buckets() - 1)),
+            t => (t - 1, None),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 struct School {
-    fish: [u64; 9],
+    model: Model,
+    fish: Vec<u64>,
 }
 
 impl fmt::Display for School {
@@ -28,49 +116,417 @@ impl fmt::Display for School {
     }
 }
 
-impl FromStr for School {
-    type Err = ParseIntError;
+/// Why a list of timers could not be read as a `School`.
+#[derive(Debug, PartialEq)]
+enum SchoolParseError {
+    InvalidTimer(ParseIntError),
+    /// A timer longer than the `max` of a newborn fish.
+    TimerOutOfRange {
+        timer: usize,
+        max: usize,
+    },
+}
 
-    fn from_str(s: &str) -> Result<Self, ParseIntError> {
-        let all_fish: Vec<u32> = s
-            .split(',')
-            .map(|ch| ch.parse().expect("Could not parse to int."))
-            .collect::<Vec<_>>();
+impl From<ParseIntError> for SchoolParseError {
+    fn from(err: ParseIntError) -> Self {
+        SchoolParseError::InvalidTimer(err)
+    }
+}
+
+impl fmt::Display for SchoolParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchoolParseError::InvalidTimer(err) => write!(f, "invalid timer: {}", err),
+            SchoolParseError::TimerOutOfRange { timer, max } => {
+                write!(f, "timer {} is longer than the {} of a newborn", timer, max)
+            }
+        }
+    }
+}
 
-        let mut fish = [0; 9];
+impl FromStr for School {
+    type Err = SchoolParseError;
 
-        for time in all_fish {
-            fish[time as usize + 1] += 1;
+    /// Parse comma separated timers of individual fish, under the default `Model`.
+    fn from_str(s: &str) -> Result<Self, SchoolParseError> {
+        let model = Model::default();
+        let all_fish: Vec<usize> = s
+            .split(',')
+            .map(|ch| ch.parse())
+            .collect::<Result<Vec<_>, _>>()?;
+        let max = model.buckets() - 1;
+        if let Some(&timer) = all_fish.iter().find(|&&timer| timer > max) {
+            return Err(SchoolParseError::TimerOutOfRange { timer, max });
         }
-        Ok(Self { fish })
+
+        Ok(Self::new(model, &all_fish))
     }
 }
 
 impl School {
+    /// Count fish by timer; panics if any timer is beyond a newborn's.
+    fn new(model: Model, timers: &[usize]) -> Self {
+        let mut fish = vec![0; model.buckets()];
+        for &time in timers {
+            assert!(
+                time < model.buckets(),
+                "timer {} out of range for {:?}",
+                time,
+                model
+            );
+            fish[time] += 1;
+        }
+        Self { model, fish }
+    }
+
     fn next_day(&self) -> Self {
-        let fish = [
-            self.fish[1],                   // 0
-            self.fish[2],                   // 1
-            self.fish[3],                   // 2
-            self.fish[4],                   // 3
-            self.fish[5],                   // 4
-            self.fish[6],                   // 5
-            self.fish[7] + self.fish[0],    // 6
-            self.fish[8],                   // 7
-            self.fish[0],                   // 8
-        ];
-        Self { fish }
-  }
+        let mut fish = vec![0; self.fish.len()];
+        for (timer, &n) in self.fish.iter().enumerate() {
+            let (next, spawned) = self.model.tick(timer);
+            fish[next] += n;
+            if let Some(newborn) = spawned {
+                fish[newborn] += n;
+            }
+        }
+        Self {
+            model: self.model,
+            fish,
+        }
+    }
 
     fn step_by(self, days: usize) -> Self {
-        (0..=days).fold(self, |school, _| school.next_day())
+        (0..days).fold(self, |school, _| school.next_day())
     }
 
     fn count(&self) -> u64 {
         self.fish.iter().sum()
     }
+
+    /// Number of fish with each timer value, from 0 up to a newborn's.
+    fn by_timer(&self) -> &[u64] {
+        &self.fish
+    }
+
+    /// The school on each day, starting from today.
+    fn history(&self) -> impl Iterator<Item = School> {
+        std::iter::successors(Some(self.clone()), |school| Some(school.next_day()))
+    }
+
+    /// Write the count of fish with each timer, and in total, for each day
+    /// from today until `days` from now, as CSV.
+    fn write_history_csv<W: Write>(&self, writer: &mut W, days: usize) -> io::Result<()> {
+        let timers = (0..self.model.buckets())
+            .map(|timer| format!(",timer_{}", timer))
+            .collect::<String>();
+        writeln!(writer, "day,total{}", timers)?;
+        for (day, school) in self.history().take(days + 1).enumerate() {
+            let counts = school
+                .by_timer()
+                .iter()
+                .map(|n| format!(",{}", n))
+                .collect::<String>();
+            writeln!(writer, "{},{}{}", day, school.count(), counts)?;
+        }
+        Ok(())
+    }
+
+    /// Matrix `M` taking one day's counts to the next, as `next_day` does:
+    /// `next[i] = Σⱼ M[i][j] · fish[j]`.
+    fn transition<T: Zero + One>(&self) -> Matrix<T> {
+        let n = self.model.buckets();
+        (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| {
+                        // fish with timer j become fish with timer i
+                        let (next, spawned) = self.model.tick(j);
+                        let k = (next == i) as usize + (spawned == Some(i)) as usize;
+                        (0..k).fold(T::zero(), |m_ij, _| m_ij + T::one())
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Total fish after `days`, found by raising the transition matrix to the
+    /// power of the number of steps (by repeated squaring), so huge numbers
+    /// of days take only O(log days) matrix products.
+    ///
+    /// Counts soon overflow `u64`, so use `BigUint` for exact totals or
+    /// `Modular` for totals modulo some number.
+    fn project<T>(&self, days: u64) -> T
+    where
+        T: Clone + Zero + One + Add<Output = T> + Mul<Output = T> + From<u64>,
+    {
+        let m: Matrix<T> = matrix_power(&self.transition(), days);
+        m.iter()
+            .flat_map(|row| {
+                row.iter()
+                    .zip(&self.fish)
+                    .map(|(m_ij, &n_j)| m_ij.clone() * T::from(n_j))
+            })
+            .fold(T::zero(), |total, n| total + n)
+    }
 }
 
+/// Step every fish individually for `days`, returning the timers of the whole
+/// school: slow, but a direct reading of the rules to check `School` against.
+#[allow(dead_code)]
+fn simulate(model: Model, timers: &[usize], days: usize) -> Vec<usize> {
+    (0..days).fold(timers.to_vec(), |timers, _| {
+        let mut newborns = Vec::new();
+        let mut next = timers
+            .into_iter()
+            .map(|timer| {
+                let (next, spawned) = match timer {
+                    0 => (model.cycle - 1, true),
+                    t => (t - 1, false),
+                };
+                if spawned {
+                    newborns.push(model.cycle + model.maturation - 1);
+                }
+                next
+            })
+            .collect::<Vec<_>>();
+        next.append(&mut newborns);
+        next
+    })
+}
+
+/// Square matrix, as rows.
+type Matrix<T> = Vec<Vec<T>>;
+
+fn matrix_product<T>(a: &Matrix<T>, b: &Matrix<T>) -> Matrix<T>
+where
+    T: Clone + Zero + Add<Output = T> + Mul<Output = T>,
+{
+    let n = a.len();
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| (0..n).fold(T::zero(), |sum, k| sum + a[i][k].clone() * b[k][j].clone()))
+                .collect()
+        })
+        .collect()
+}
+
+/// `m` raised to the power `n`, by repeated squaring.
+fn matrix_power<T>(m: &Matrix<T>, n: u64) -> Matrix<T>
+where
+    T: Clone + Zero + One + Add<Output = T> + Mul<Output = T>,
+{
+    let identity = (0..m.len())
+        .map(|i| {
+            (0..m.len())
+                .map(|j| if i == j { T::one() } else { T::zero() })
+                .collect()
+        })
+        .collect();
+    let (mut result, mut square, mut n) = (identity, m.clone(), n);
+    while n > 0 {
+        if n & 1 == 1 {
+            result = matrix_product(&result, &square);
+        }
+        square = matrix_product(&square, &square);
+        n >>= 1;
+    }
+    result
+}
+
+/// Whole numbers modulo `M`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Modular<const M: u64>(u64);
+
+impl<const M: u64> From<u64> for Modular<M> {
+    fn from(n: u64) -> Self {
+        Self(n % M)
+    }
+}
+
+impl<const M: u64> fmt::Display for Modular<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<const M: u64> Add for Modular<M> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(((self.0 as u128 + other.0 as u128) % M as u128) as u64)
+    }
+}
+
+impl<const M: u64> Mul for Modular<M> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self(((self.0 as u128 * other.0 as u128) % M as u128) as u64)
+    }
+}
+
+impl<const M: u64> Zero for Modular<M> {
+    fn zero() -> Self {
+        Self(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl<const M: u64> One for Modular<M> {
+    fn one() -> Self {
+        Self(1 % M)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn parses_timers() {
+        let school = School::from_str("3,4,3,1,2").unwrap();
+        assert_eq!(school.fish, vec![0, 1, 1, 2, 1, 0, 0, 0, 0]);
+        assert_eq!(school.count(), 5);
+        assert!(School::from_str("3,4,x").is_err());
+        assert!(School::from_str("3,9").is_err());
+        assert_eq!(
+            School::from_str("3,9").unwrap_err().to_string(),
+            "timer 9 is longer than the 8 of a newborn"
+        );
+    }
+
+    #[test]
+    fn steps_single_days() {
+        let school = School::from_str("3,4,3,1,2").unwrap();
+        let after_1_day = School::from_str("2,3,2,0,1").unwrap();
+        let after_2_days = School::from_str("1,2,1,6,0,8").unwrap();
+        let after_3_days = School::from_str("0,1,0,5,6,7,8").unwrap();
+
+        assert_eq!(school.clone().step_by(0), school);
+        assert_eq!(school.next_day(), after_1_day);
+        assert_eq!(school.clone().step_by(2), after_2_days);
+        assert_eq!(school.clone().step_by(3), after_3_days);
+        assert_eq!(
+            after_2_days.to_string(),
+            "[(0, 1), (1, 2), (2, 1), (3, 0), (4, 0), (5, 0), (6, 1), (7, 0), (8, 1)]"
+        );
+    }
+
+    #[test]
+    fn example() {
+        let school = School::from_str("3,4,3,1,2").unwrap();
+        assert_eq!(school.clone().step_by(18).count(), 26);
+        assert_eq!(school.clone().step_by(80).count(), 5934);
+        assert_eq!(school.clone().step_by(256).count(), 26984457539);
+
+        assert_eq!(school.project::<u64>(18), 26);
+        assert_eq!(school.project::<u64>(80), 5934);
+        assert_eq!(school.project::<u64>(256), 26984457539);
+    }
+
+    #[test]
+    fn matches_brute_force_simulation() {
+        let timers = [3, 4, 3, 1, 2];
+        for model in [
+            Model::default(),
+            Model::new(1, 0),
+            Model::new(3, 1),
+            Model::new(5, 4),
+        ] {
+            let timers = timers.map(|t| t % model.buckets());
+            let school = School::new(model, &timers);
+            for days in [0, 1, 2, 7, 15] {
+                let simulated = simulate(model, &timers, days);
+                let expected = School::new(model, &simulated);
+                assert_eq!(school.clone().step_by(days), expected);
+                assert_eq!(school.project::<u64>(days as u64), simulated.len() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn doubling_model() {
+        // every fish spawns every day, and newborns spawn the day after
+        let school = School::new(Model::new(1, 0), &[0, 0, 0]);
+        assert_eq!(school.clone().step_by(10).count(), 3 * 1024);
+        assert_eq!(school.project::<u64>(10), 3 * 1024);
+    }
+
+    #[test]
+    fn history_of_daily_states() {
+        let school = School::from_str("3,4,3,1,2").unwrap();
+        let totals = school
+            .history()
+            .take(19)
+            .map(|s| s.count())
+            .collect::<Vec<_>>();
+        assert_eq!(totals.first(), Some(&5));
+        assert_eq!(totals.last(), Some(&26));
+        assert_eq!(
+            school.history().nth(2).unwrap().by_timer(),
+            &[1, 2, 1, 0, 0, 0, 1, 0, 1]
+        );
+
+        let mut csv = Vec::new();
+        school.write_history_csv(&mut csv, 2).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "day,total,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8\n\
+             0,5,0,1,1,2,1,0,0,0,0\n\
+             1,5,1,1,2,1,0,0,0,0,0\n\
+             2,6,1,2,1,0,0,0,1,0,1\n"
+        );
+    }
+
+    #[test]
+    fn growth_rates() {
+        assert!((Model::new(1, 0).growth_rate() - 2.0).abs() < 1e-12);
+        assert!((Model::new(2, 0).growth_rate() - 2.0_f64.sqrt()).abs() < 1e-12);
+        // the golden ratio, as for Fibonacci rabbits
+        assert!((Model::new(1, 1).growth_rate() - (1.0 + 5.0_f64.sqrt()) / 2.0).abs() < 1e-12);
+
+        let model = Model::default();
+        let rate = model.growth_rate();
+        assert!((rate.powi(9) - rate.powi(2) - 1.0).abs() < 1e-12);
+
+        let school = School::from_str("3,4,3,1,2").unwrap();
+        // averaged over many days, to smooth out the weekly spawning pulses
+        let ratio = school.project::<u64>(400) as f64 / school.project::<u64>(100) as f64;
+        assert!((ratio.powf(1.0 / 300.0) - rate).abs() < 1e-4);
+    }
+
+    #[test]
+    fn projection_matches_daily_steps() {
+        let school = School::from_str("3,4,3,1,2").unwrap();
+        for days in [0, 1, 18, 80, 256] {
+            assert_eq!(
+                school.project::<u64>(days),
+                school.clone().step_by(days as usize).count()
+            );
+            assert_eq!(
+                school.project::<BigUint>(days),
+                BigUint::from(school.clone().step_by(days as usize).count())
+            );
+        }
+    }
+
+    #[test]
+    fn projection_beyond_u64() {
+        let school = School::from_str("3,4,3,1,2").unwrap();
+        let exact = school.project::<BigUint>(1000);
+        assert!(exact > BigUint::from(u64::MAX));
+
+        let modulus = 1_000_000_007_u64;
+        assert_eq!(
+            school.project::<Modular<1_000_000_007>>(1000),
+            Modular((exact % modulus).try_into().unwrap())
+        );
+    }
+}
 
 #[allow(dead_code)]
 mod input {
@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::RangeInclusive;
 use std::path::Path;
 
 fn main() {
@@ -8,84 +10,983 @@ fn main() {
 
     let positions = Positions::from_list(&input[0]);
 
-    let metric_part_1 = |x: i32, y: i32| ((x - y) as i32).abs();
-    println!("{:?}", positions.minimum_displacements(metric_part_1));
+    println!("{}", positions.minimum_linear().unwrap());
+    assert_eq!(
+        positions.minimum_linear(),
+        positions.minimum_displacements(&Linear)
+    );
 
-    let metric_part_2 = |x: i32, y: i32| {
-        let d = ((x - y) as i32).abs();
+    println!("{}", positions.minimum_triangular().unwrap());
+    assert_eq!(
+        positions.minimum_triangular(),
+        positions.minimum_displacements(&Triangular)
+    );
+    assert_eq!(
+        positions.minimum(&Triangular),
+        positions.minimum_displacements(&Triangular)
+    );
+
+    let swarm = Swarm::new(input[0].iter().map(|&x| [x]).collect());
+    assert_eq!(
+        swarm.minimum_separable(&Linear).map(|meeting| meeting.fuel),
+        positions.minimum_linear().map(|alignment| alignment.fuel)
+    );
+}
+
+/// Fuel is counted in `u128`, wide enough for any built-in metric between
+/// two `i32` positions, multiplied by any `u64` cost or number of crabs.
+type Fuel = u128;
+
+/// Fuel for a crab to move from `x` to `y`.
+trait DistanceMetric {
+    fn distance(&self, x: i32, y: i32) -> Fuel;
+
+    /// Whether the distance is convex in `y` for every `x`, so a total of
+    /// such distances has a single minimum that can be searched for.
+    fn is_convex(&self) -> bool {
+        false
+    }
+
+    /// Fuel for all of `crabs` to move to `y`. Metrics with a closed form
+    /// answer from the prefix sums of `crabs` rather than visiting every crab.
+    fn total(&self, crabs: &Positions, y: i32) -> Result<Fuel, AlignmentError> {
+        crabs.per_crab_total(y, self)
+    }
+}
+
+/// Any closure can be used as a metric, though nothing is assumed about its shape.
+impl<F: Fn(i32, i32) -> Fuel> DistanceMetric for F {
+    fn distance(&self, x: i32, y: i32) -> Fuel {
+        self(x, y)
+    }
+}
+
+/// One unit of fuel per step.
+#[derive(Debug, Clone, Copy)]
+struct Linear;
+
+impl DistanceMetric for Linear {
+    fn distance(&self, x: i32, y: i32) -> Fuel {
+        x.abs_diff(y) as Fuel
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn total(&self, crabs: &Positions, y: i32) -> Result<Fuel, AlignmentError> {
+        match crabs.linear_total(y) {
+            Some(total) => Ok(total),
+            None => crabs.per_crab_total(y, self),
+        }
+    }
+}
+
+/// Each step costs one more than the last: `1 + 2 + ... + d`.
+#[derive(Debug, Clone, Copy)]
+struct Triangular;
+
+impl DistanceMetric for Triangular {
+    fn distance(&self, x: i32, y: i32) -> Fuel {
+        let d = x.abs_diff(y) as Fuel;
         d * (d + 1) / 2
-    };
-    println!("{:?}", positions.minimum_displacements(metric_part_2));
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn total(&self, crabs: &Positions, y: i32) -> Result<Fuel, AlignmentError> {
+        let closed_form = crabs
+            .squared_total(y)
+            .zip(crabs.linear_total(y))
+            .and_then(|(squared, linear)| squared.checked_add(linear));
+        match closed_form {
+            Some(total) => Ok(total / 2),
+            None => crabs.per_crab_total(y, self),
+        }
+    }
 }
 
-#[derive(Debug)]
+/// Square of the distance moved.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+struct Quadratic;
+
+impl DistanceMetric for Quadratic {
+    fn distance(&self, x: i32, y: i32) -> Fuel {
+        let d = x.abs_diff(y) as Fuel;
+        d * d
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn total(&self, crabs: &Positions, y: i32) -> Result<Fuel, AlignmentError> {
+        match crabs.squared_total(y) {
+            Some(total) => Ok(total),
+            None => crabs.per_crab_total(y, self),
+        }
+    }
+}
+
+/// Another metric with every distance multiplied by `cost`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+struct Scaled<M> {
+    metric: M,
+    cost: u64,
+}
+
+impl<M: DistanceMetric> DistanceMetric for Scaled<M> {
+    fn distance(&self, x: i32, y: i32) -> Fuel {
+        self.cost as Fuel * self.metric.distance(x, y)
+    }
+
+    fn is_convex(&self) -> bool {
+        self.metric.is_convex()
+    }
+
+    fn total(&self, crabs: &Positions, y: i32) -> Result<Fuel, AlignmentError> {
+        self.metric
+            .total(crabs, y)?
+            .checked_mul(self.cost as Fuel)
+            .ok_or(AlignmentError::Overflow { position: y })
+    }
+}
+
+/// A closure declared by the caller to be convex in `y`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+struct Convex<F>(F);
+
+impl<F: Fn(i32, i32) -> Fuel> DistanceMetric for Convex<F> {
+    fn distance(&self, x: i32, y: i32) -> Fuel {
+        (self.0)(x, y)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+/// Crabs grouped by starting position.
+#[derive(Debug, PartialEq)]
 struct Positions {
-    counts: HashMap<i32, i32>,
+    /// Every distinct starting position, in increasing order.
+    xs: Vec<i32>,
+    /// Number of crabs starting at each of `xs`.
+    weights: Vec<u64>,
+    /// Running totals over `xs`, unless they are too large to hold.
+    prefix: Option<PrefixSums>,
+}
+
+/// Totals of `w`, `w x` and `w x²` over the first `k` positions, at index `k`,
+/// from which the fuel for the closed-form metrics follows in constant time
+/// once `y` has been located among the positions.
+#[derive(Debug, PartialEq)]
+struct PrefixSums {
+    weights: Vec<i128>,
+    moments: Vec<i128>,
+    squares: Vec<i128>,
+}
+
+impl PrefixSums {
+    fn new(xs: &[i32], weights: &[u64]) -> Option<Self> {
+        let mut sums = Self {
+            weights: vec![0],
+            moments: vec![0],
+            squares: vec![0],
+        };
+        for (&x, &w) in xs.iter().zip(weights) {
+            let (x, w) = (x as i128, w as i128);
+            let (weight, moment, square) = (
+                sums.weights.last()?,
+                sums.moments.last()?,
+                sums.squares.last()?,
+            );
+            let next = (
+                weight.checked_add(w)?,
+                moment.checked_add(w * x)?,
+                square.checked_add(w * x * x)?,
+            );
+            sums.weights.push(next.0);
+            sums.moments.push(next.1);
+            sums.squares.push(next.2);
+        }
+        Some(sums)
+    }
 }
 
 impl Positions {
     fn from_list(list: &[i32]) -> Self {
-        let counts = list.iter().fold(HashMap::new(), |mut map, &k| {
-            *map.entry(k).or_insert(0) += 1;
-            map
-        });
-        Self { counts }
+        let mut sorted = list.to_vec();
+        sorted.sort_unstable();
+        let (mut xs, mut weights) = (Vec::new(), Vec::<u64>::new());
+        for x in sorted {
+            match weights.last_mut() {
+                Some(w) if xs.last() == Some(&x) => *w += 1,
+                _ => {
+                    xs.push(x);
+                    weights.push(1);
+                }
+            }
+        }
+        let prefix = PrefixSums::new(&xs, &weights);
+        Self {
+            xs,
+            weights,
+            prefix,
+        }
     }
 
-    fn unique(&self) -> Vec<i32> {
-        self.counts.keys().copied().collect()
+    fn total_elements(&self) -> u64 {
+        self.weights.iter().sum()
     }
 
-    fn weights(&self) -> Vec<i32> {
-        self.counts.values().copied().collect()
+    /// Fuel for every crab to move to `y`, or an error if the total is too
+    /// large to count.
+    fn total_displacement(
+        &self,
+        y: i32,
+        distance_metric: &impl DistanceMetric,
+    ) -> Result<Fuel, AlignmentError> {
+        distance_metric.total(self, y)
     }
 
-    fn total_elements(&self) -> i32 {
-        self.counts.values().sum()
+    /// Fuel for every crab to move to `y`, one position at a time.
+    fn per_crab_total<M: DistanceMetric + ?Sized>(
+        &self,
+        y: i32,
+        distance_metric: &M,
+    ) -> Result<Fuel, AlignmentError> {
+        let overflow = AlignmentError::Overflow { position: y };
+        self.xs
+            .iter()
+            .zip(&self.weights)
+            .try_fold(0 as Fuel, |total, (x, w)| {
+                (*w as Fuel)
+                    .checked_mul(distance_metric.distance(*x, y))
+                    .and_then(|fuel| total.checked_add(fuel))
+                    .ok_or(overflow)
+            })
     }
 
-    fn minimum_displacements(&self, distance_metric: fn(i32, i32) -> i32) -> Option<i32> {
-        let xs = self.unique();
-        let ws = self.weights();
-        match (xs.iter().min(), xs.iter().max()) {
-            (Some(min_x), Some(max_x)) => (*min_x..=*max_x)
-                .map(|y| {
-                    xs.iter()
-                        .zip(ws.iter())
-                        .map(|(x, w)| w * distance_metric(*x, y))
-                        .sum()
-                })
-                .min(),
-            (_, _) => None,
+    /// Total of `|x - y|` over every crab, from the prefix sums either side of `y`.
+    fn linear_total(&self, y: i32) -> Option<Fuel> {
+        let sums = self.prefix.as_ref()?;
+        let (k, n) = (self.xs.partition_point(|&x| x <= y), self.xs.len());
+        let y = y as i128;
+        let below = y
+            .checked_mul(sums.weights[k])?
+            .checked_sub(sums.moments[k])?;
+        let above = (sums.moments[n] - sums.moments[k])
+            .checked_sub(y.checked_mul(sums.weights[n] - sums.weights[k])?)?;
+        Fuel::try_from(below.checked_add(above)?).ok()
+    }
+
+    /// Total of `(x - y)²` over every crab, expanded as `Σw x² - 2y Σw x + y² Σw`.
+    fn squared_total(&self, y: i32) -> Option<Fuel> {
+        let sums = self.prefix.as_ref()?;
+        let n = self.xs.len();
+        let y = y as i128;
+        let total = sums.squares[n]
+            .checked_sub(y.checked_mul(2)?.checked_mul(sums.moments[n])?)?
+            .checked_add(y.checked_mul(y)?.checked_mul(sums.weights[n])?)?;
+        Fuel::try_from(total).ok()
+    }
+
+    /// Position with at least half of the crabs on either side of it.
+    #[allow(dead_code)]
+    fn median(&self) -> Option<i32> {
+        self.middle().map(|(lower, _)| lower)
+    }
+
+    /// Positions of the two middle crabs in order, the same crab if there
+    /// is an odd number of them.
+    fn middle(&self) -> Option<(i32, i32)> {
+        let total = self.total_elements();
+        // position of the crab with `k` crabs before it
+        let crab = |k: u64| {
+            let mut seen = 0;
+            self.xs.iter().zip(&self.weights).find_map(|(x, w)| {
+                seen += w;
+                (seen > k).then_some(*x)
+            })
+        };
+        Some((crab(total.checked_sub(1)? / 2)?, crab(total / 2)?))
+    }
+
+    fn mean(&self) -> Option<f64> {
+        match self.total_elements() {
+            0 => None,
+            n => {
+                let sum: i128 = self
+                    .xs
+                    .iter()
+                    .zip(&self.weights)
+                    .map(|(x, w)| *x as i128 * *w as i128)
+                    .sum();
+                Some(sum as f64 / n as f64)
+            }
+        }
+    }
+
+    fn bounds(&self) -> Result<(i32, i32), AlignmentError> {
+        match (self.xs.first(), self.xs.last()) {
+            (Some(min_x), Some(max_x)) => Ok((*min_x, *max_x)),
+            _ => Err(AlignmentError::NoCrabs),
         }
     }
+
+    /// Alignment at `y`, a position known to need the least fuel, widened
+    /// to include any neighbouring positions needing the same fuel. The
+    /// total of a convex metric is flat only around its minimum, so the
+    /// fuel falls towards `y` from either side and the ends of the flat can
+    /// be found by binary search.
+    fn aligned_around(
+        &self,
+        y: i32,
+        distance_metric: &impl DistanceMetric,
+    ) -> Result<Alignment, AlignmentError> {
+        let fuel = self.total_displacement(y, distance_metric)?;
+        let (min_x, max_x) = self.bounds()?;
+        let is_tied = |y: i64| self.total_displacement(y as i32, distance_metric) == Ok(fuel);
+        let low = first_where(min_x as i64, y as i64, is_tied);
+        let high = first_where(y as i64 + 1, max_x as i64 + 1, |y| {
+            y > max_x as i64 || !is_tied(y)
+        }) - 1;
+        Ok(Alignment::new(
+            self,
+            low as i32..=high as i32,
+            fuel,
+            distance_metric,
+        ))
+    }
+
+    /// Position needing the least fuel of `candidates`, the first if tied.
+    fn cheapest(
+        &self,
+        candidates: impl IntoIterator<Item = i32>,
+        distance_metric: &impl DistanceMetric,
+    ) -> Result<i32, AlignmentError> {
+        let mut best: Option<(Fuel, i32)> = None;
+        for y in candidates {
+            let fuel = self.total_displacement(y, distance_metric)?;
+            if best.is_none_or(|(least, _)| fuel < least) {
+                best = Some((fuel, y));
+            }
+        }
+        best.map(|(_, y)| y).ok_or(AlignmentError::NoCrabs)
+    }
+
+    /// Least fuel to align when fuel is the distance moved. The total is
+    /// minimised at the median, where moving either way takes more crabs
+    /// further away than it brings closer, and is the same anywhere between
+    /// the two middle crabs.
+    fn minimum_linear(&self) -> Result<Alignment, AlignmentError> {
+        let (lower, upper) = self.middle().ok_or(AlignmentError::NoCrabs)?;
+        let fuel = self.total_displacement(lower, &Linear)?;
+        Ok(Alignment::new(self, lower..=upper, fuel, &Linear))
+    }
+
+    /// Least fuel to align when moving `d` steps costs `1 + 2 + ... + d`.
+    /// The real-valued optimum lies within ½ of the mean, so one of the
+    /// whole numbers either side of the mean is optimal.
+    fn minimum_triangular(&self) -> Result<Alignment, AlignmentError> {
+        let mean = self.mean().ok_or(AlignmentError::NoCrabs)?;
+        let y = self.cheapest([mean.floor() as i32, mean.ceil() as i32], &Triangular)?;
+        self.aligned_around(y, &Triangular)
+    }
+
+    /// Least fuel to align for any metric convex in `y` (as the total is then
+    /// convex too), by ternary search between the outermost crabs.
+    fn minimum_convex(
+        &self,
+        distance_metric: &impl DistanceMetric,
+    ) -> Result<Alignment, AlignmentError> {
+        let cost = |y: i32| self.total_displacement(y, distance_metric);
+        let (mut low, mut high) = self.bounds()?;
+        while high.abs_diff(low) > 2 {
+            let third = (high.abs_diff(low) / 3) as i32;
+            let (a, b) = (low + third, high - third);
+            match cost(a)?.cmp(&cost(b)?) {
+                Ordering::Less => high = b - 1,
+                Ordering::Greater => low = a + 1,
+                Ordering::Equal => (low, high) = (a, b),
+            }
+        }
+        let y = self.cheapest(low..=high, distance_metric)?;
+        self.aligned_around(y, distance_metric)
+    }
+
+    /// Least fuel to align, searching only when the metric is known to be convex.
+    fn minimum(&self, distance_metric: &impl DistanceMetric) -> Result<Alignment, AlignmentError> {
+        if distance_metric.is_convex() {
+            self.minimum_convex(distance_metric)
+        } else {
+            self.minimum_displacements(distance_metric)
+        }
+    }
+
+    /// Least fuel to align, by trying every position between the outermost
+    /// crabs. Ties of a metric that is not convex may be apart, in which
+    /// case only the first run of them is kept.
+    fn minimum_displacements(
+        &self,
+        distance_metric: &impl DistanceMetric,
+    ) -> Result<Alignment, AlignmentError> {
+        let (min_x, max_x) = self.bounds()?;
+        let costs = (min_x..=max_x)
+            .map(|y| Ok((y, self.total_displacement(y, distance_metric)?)))
+            .collect::<Result<Vec<(i32, Fuel)>, AlignmentError>>()?;
+        let fuel = costs.iter().map(|(_, fuel)| *fuel).min().unwrap();
+        let mut best = costs
+            .iter()
+            .skip_while(|(_, f)| *f != fuel)
+            .take_while(|(_, f)| *f == fuel)
+            .map(|(y, _)| *y);
+        let low = best.next().unwrap();
+        let high = best.last().unwrap_or(low);
+        Ok(Alignment::new(self, low..=high, fuel, distance_metric))
+    }
 }
 
+/// First of `low..=high` for which `holds` is true, given that it is true
+/// for `high` and stays true from the first onwards.
+fn first_where(mut low: i64, mut high: i64, holds: impl Fn(i64) -> bool) -> i64 {
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if holds(mid) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    high
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AlignmentError {
+    /// There is nobody to align.
+    NoCrabs,
+    /// The total fuel to align at `position` does not fit in a `Fuel`.
+    Overflow { position: i32 },
+}
+
+impl fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignmentError::NoCrabs => write!(f, "no crabs to align"),
+            AlignmentError::Overflow { position } => {
+                write!(f, "fuel to align at {} is too large to count", position)
+            }
+        }
+    }
+}
+
+/// Where the crabs should meet and what it costs.
+#[derive(Debug, PartialEq)]
+struct Alignment {
+    /// Every position needing the least fuel.
+    positions: RangeInclusive<i32>,
+    /// Total fuel to align at any of `positions`.
+    fuel: Fuel,
+    /// Fuel used by the crabs starting at each position, when aligning at
+    /// the first of `positions`, in order of starting position.
+    breakdown: Vec<CrabFuel>,
+}
+
+#[derive(Debug, PartialEq)]
+struct CrabFuel {
+    position: i32,
+    crabs: u64,
+    fuel_per_crab: Fuel,
+}
+
+impl Alignment {
+    fn new(
+        crabs: &Positions,
+        positions: RangeInclusive<i32>,
+        fuel: Fuel,
+        distance_metric: &impl DistanceMetric,
+    ) -> Self {
+        let breakdown = crabs
+            .xs
+            .iter()
+            .zip(&crabs.weights)
+            .map(|(&x, &w)| CrabFuel {
+                position: x,
+                crabs: w,
+                fuel_per_crab: distance_metric.distance(x, *positions.start()),
+            })
+            .collect();
+        Self {
+            positions,
+            fuel,
+            breakdown,
+        }
+    }
+}
+
+impl fmt::Display for Alignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (low, high) = (self.positions.start(), self.positions.end());
+        match low == high {
+            true => write!(f, "align at {} using {} fuel", low, self.fuel),
+            false => write!(f, "align at {} to {} using {} fuel", low, high, self.fuel),
+        }
+    }
+}
+
+/// Crabs free to move along `N` axes.
+#[derive(Debug)]
+struct Swarm<const N: usize> {
+    crabs: Vec<[i32; N]>,
+}
+
+/// Where a swarm should meet when fuel is a separable metric, one summed
+/// over the movement along each axis (so `Linear` gives Manhattan distance).
+#[derive(Debug, PartialEq)]
+struct MeetingPoint<const N: usize> {
+    point: [i32; N],
+    fuel: Fuel,
+}
+
+impl<const N: usize> Swarm<N> {
+    fn new(crabs: Vec<[i32; N]>) -> Self {
+        Self { crabs }
+    }
+
+    /// Positions of the crabs along one axis.
+    fn axis(&self, axis: usize) -> Positions {
+        let xs = self.crabs.iter().map(|crab| crab[axis]).collect::<Vec<_>>();
+        Positions::from_list(&xs)
+    }
+
+    /// Least fuel to meet when fuel is `distance_metric` summed over the
+    /// axes. The axes can then be aligned independently, so for `Linear`
+    /// this is the per-axis median.
+    fn minimum_separable(
+        &self,
+        distance_metric: &impl DistanceMetric,
+    ) -> Result<MeetingPoint<N>, AlignmentError> {
+        let mut meeting = MeetingPoint {
+            point: [0; N],
+            fuel: 0,
+        };
+        for axis in 0..N {
+            let alignment = self.axis(axis).minimum(distance_metric)?;
+            meeting.point[axis] = *alignment.positions.start();
+            meeting.fuel =
+                meeting
+                    .fuel
+                    .checked_add(alignment.fuel)
+                    .ok_or(AlignmentError::Overflow {
+                        position: *alignment.positions.start(),
+                    })?;
+        }
+        Ok(meeting)
+    }
+
+    /// Point minimising the total straight-line distance to every crab, and
+    /// that total, by Weiszfeld's iteration from the centroid until a step
+    /// moves less than `tolerance`. A step landing on a crab uses Vardi and
+    /// Zhang's correction, stopping there if the pull of the other crabs is
+    /// no stronger than that of the crabs already there.
+    #[allow(dead_code)]
+    fn geometric_median(&self, tolerance: f64) -> Option<([f64; N], f64)> {
+        let crabs = self
+            .crabs
+            .iter()
+            .map(|crab| crab.map(f64::from))
+            .collect::<Vec<_>>();
+        let distance = |a: &[f64; N], b: &[f64; N]| {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f64>()
+                .sqrt()
+        };
+        let total = |y: &[f64; N]| crabs.iter().map(|x| distance(x, y)).sum::<f64>();
+
+        if crabs.is_empty() {
+            return None;
+        }
+        let mut y = [0.0; N];
+        for crab in &crabs {
+            for axis in 0..N {
+                y[axis] += crab[axis] / crabs.len() as f64;
+            }
+        }
+        for _ in 0..10_000 {
+            let (mut weighted, mut inverse, mut coincident) = ([0.0; N], 0.0, 0.0);
+            for x in &crabs {
+                let d = distance(x, &y);
+                if d == 0.0 {
+                    coincident += 1.0;
+                    continue;
+                }
+                for axis in 0..N {
+                    weighted[axis] += x[axis] / d;
+                }
+                inverse += 1.0 / d;
+            }
+            if inverse == 0.0 {
+                break;
+            }
+            let mut next = weighted.map(|w| w / inverse);
+            if coincident > 0.0 {
+                let pull = distance(&next, &y) * inverse;
+                if pull <= coincident {
+                    break;
+                }
+                let keep = coincident / pull;
+                for axis in 0..N {
+                    next[axis] = (1.0 - keep) * next[axis] + keep * y[axis];
+                }
+            }
+            let step = distance(&next, &y);
+            y = next;
+            if step < tolerance {
+                break;
+            }
+        }
+        Some((y, total(&y)))
+    }
+}
+
+#[cfg(test)]
 mod tests {
     #[test]
     fn example() {
-        use crate::Positions;
+        use crate::{Positions, Triangular};
 
-        let example_input = vec![16,1,2,0,4,2,7,1,2,14];
+        let example_input = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
         let positions = Positions::from_list(&example_input);
 
-        let metric_part_1 = |x: i32, y: i32| ((x - y) as i32).abs();
+        let metric_part_1 = |x: i32, y: i32| x.abs_diff(y) as u128;
+        let alignment = positions.minimum_displacements(&metric_part_1).unwrap();
+        assert_eq!(37, alignment.fuel);
+        assert_eq!(2..=2, alignment.positions);
+
+        let alignment = positions.minimum_displacements(&Triangular).unwrap();
+        assert_eq!(168, alignment.fuel);
+        assert_eq!(5..=5, alignment.positions);
+    }
+
+    #[test]
+    fn specialised_solvers() {
+        use crate::{Alignment, AlignmentError, Linear, Positions, Triangular};
+
+        let positions = Positions::from_list(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]);
+        assert_eq!(positions.median(), Some(2));
+        assert_eq!(positions.mean(), Some(4.9));
+        let fuel = |alignment: Result<Alignment, _>| alignment.map(|a| a.fuel);
+        assert_eq!(fuel(positions.minimum_linear()), Ok(37));
+        assert_eq!(fuel(positions.minimum_triangular()), Ok(168));
+        assert_eq!(fuel(positions.minimum_convex(&Linear)), Ok(37));
+        assert_eq!(fuel(positions.minimum_convex(&Triangular)), Ok(168));
+
+        let empty = Positions::from_list(&[]);
+        assert_eq!(empty.minimum_linear(), Err(AlignmentError::NoCrabs));
+        assert_eq!(empty.minimum_triangular(), Err(AlignmentError::NoCrabs));
+        assert_eq!(empty.minimum_convex(&Linear), Err(AlignmentError::NoCrabs));
+        assert_eq!(
+            empty.minimum_displacements(&Linear),
+            Err(AlignmentError::NoCrabs)
+        );
+    }
+
+    #[test]
+    fn alignment_breakdown_and_ties() {
+        use crate::{Alignment, CrabFuel, Linear, MeetingPoint, Positions, Swarm};
+
+        let positions = Positions::from_list(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]);
+        let alignment = positions.minimum_linear().unwrap();
+        let per_crab = alignment
+            .breakdown
+            .iter()
+            .map(|crab| (crab.position, crab.crabs, crab.fuel_per_crab))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            per_crab,
+            vec![
+                (0, 1, 2),
+                (1, 2, 1),
+                (2, 3, 0),
+                (4, 1, 2),
+                (7, 1, 5),
+                (14, 1, 12),
+                (16, 1, 14)
+            ]
+        );
+        assert_eq!(
+            alignment
+                .breakdown
+                .iter()
+                .map(|crab| crab.crabs as u128 * crab.fuel_per_crab)
+                .sum::<u128>(),
+            alignment.fuel
+        );
+        assert_eq!(alignment.to_string(), "align at 2 using 37 fuel");
+
+        // any point between the two middle crabs is as good as any other
+        let positions = Positions::from_list(&[0, 3, 7, 10]);
+        let expected = Alignment {
+            positions: 3..=7,
+            fuel: 14,
+            breakdown: vec![
+                CrabFuel {
+                    position: 0,
+                    crabs: 1,
+                    fuel_per_crab: 3,
+                },
+                CrabFuel {
+                    position: 3,
+                    crabs: 1,
+                    fuel_per_crab: 0,
+                },
+                CrabFuel {
+                    position: 7,
+                    crabs: 1,
+                    fuel_per_crab: 4,
+                },
+                CrabFuel {
+                    position: 10,
+                    crabs: 1,
+                    fuel_per_crab: 7,
+                },
+            ],
+        };
+        assert_eq!(positions.minimum_displacements(&Linear), Ok(expected));
+        assert_eq!(
+            positions.minimum_linear(),
+            positions.minimum_displacements(&Linear)
+        );
+        assert_eq!(
+            positions.minimum_convex(&Linear),
+            positions.minimum_displacements(&Linear)
+        );
+
+        // a tie across a billion positions is found without visiting them
+        let positions = Positions::from_list(&[-500_000_000, 500_000_000]);
+        let alignment = positions.minimum_linear().unwrap();
+        assert_eq!(alignment.positions, -500_000_000..=500_000_000);
+        assert_eq!(alignment.fuel, 1_000_000_000);
+        assert_eq!(
+            alignment.to_string(),
+            "align at -500000000 to 500000000 using 1000000000 fuel"
+        );
+        assert_eq!(positions.minimum_convex(&Linear), Ok(alignment));
+        let swarm = Swarm::new(vec![[i32::MIN, 0], [i32::MAX, 5]]);
+        assert_eq!(
+            swarm.minimum_separable(&Linear),
+            Ok(MeetingPoint {
+                point: [i32::MIN, 0],
+                fuel: u32::MAX as u128 + 5
+            })
+        );
+    }
+
+    #[test]
+    fn distance_metrics() {
+        use crate::{Convex, DistanceMetric, Linear, Positions, Quadratic, Scaled, Triangular};
+
+        assert_eq!(Linear.distance(16, 5), 11);
+        assert_eq!(Triangular.distance(16, 5), 66);
+        assert_eq!(Quadratic.distance(16, 5), 121);
+        let scaled = Scaled {
+            metric: Triangular,
+            cost: 3,
+        };
+        assert_eq!(scaled.distance(16, 5), 198);
+        assert!(scaled.is_convex());
+
+        // closures may capture parameters, but are only searched when declared convex
+        let cost = 3;
+        let capturing = |x: i32, y: i32| cost * x.abs_diff(y) as u128;
+        assert!(!capturing.is_convex());
+        assert!(Convex(capturing).is_convex());
+
+        let positions = Positions::from_list(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]);
+        let expected = positions.minimum_displacements(&capturing);
+        assert_eq!(expected.as_ref().map(|a| a.fuel), Ok(111));
+        assert_eq!(positions.minimum(&capturing), expected);
+        assert_eq!(positions.minimum(&Convex(capturing)), expected);
+        assert_eq!(positions.minimum(&scaled).map(|a| a.fuel), Ok(504));
+    }
+
+    #[test]
+    fn extreme_positions() {
+        use crate::{AlignmentError, Linear, Positions, Quadratic, Triangular};
+
+        let positions = Positions::from_list(&[i32::MIN, i32::MAX, i32::MAX]);
+        let alignment = positions.minimum_linear().unwrap();
+        assert_eq!(alignment.positions, i32::MAX..=i32::MAX);
+        assert_eq!(alignment.fuel, u32::MAX as u128);
         assert_eq!(
-            Some(37),
-            positions.minimum_displacements(metric_part_1)
+            positions.minimum_triangular(),
+            positions.minimum_convex(&Triangular)
+        );
+        assert_eq!(
+            positions.minimum_convex(&Linear).map(|a| a.fuel),
+            Ok(u32::MAX as u128)
+        );
+        let alignment = positions.minimum(&Quadratic).unwrap();
+        assert_eq!(alignment.positions, 715_827_882..=715_827_882);
+
+        // too much fuel to count is an error rather than a wrapped total
+        let ruinous = |x: i32, y: i32| if x == y { 0 } else { u128::MAX / 2 + 1 };
+        let positions = Positions::from_list(&[0, 10, 10]);
+        let error = positions.minimum_displacements(&ruinous).unwrap_err();
+        assert_eq!(error, AlignmentError::Overflow { position: 0 });
+        assert_eq!(
+            error.to_string(),
+            "fuel to align at 0 is too large to count"
+        );
+    }
+
+    #[test]
+    fn sorted_positions_and_prefix_sums() {
+        use crate::{DistanceMetric, Linear, Positions, Quadratic, Scaled, Triangular};
+
+        let positions = Positions::from_list(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]);
+        assert_eq!(positions.xs, vec![0, 1, 2, 4, 7, 14, 16]);
+        assert_eq!(positions.weights, vec![1, 2, 3, 1, 1, 1, 1]);
+        assert_eq!(
+            positions,
+            Positions::from_list(&[14, 2, 7, 1, 2, 4, 0, 2, 1, 16])
         );
 
-        let metric_part_2 = |x: i32, y: i32| {
-            let d = ((x - y) as i32).abs();
-            (0..=d).sum()
+        let scaled = Scaled {
+            metric: Triangular,
+            cost: 7,
         };
+        for y in -5..25 {
+            let per_crab = |metric: &dyn DistanceMetric| positions.per_crab_total(y, metric);
+            assert_eq!(Linear.total(&positions, y), per_crab(&Linear));
+            assert_eq!(Triangular.total(&positions, y), per_crab(&Triangular));
+            assert_eq!(Quadratic.total(&positions, y), per_crab(&Quadratic));
+            assert_eq!(scaled.total(&positions, y), per_crab(&scaled));
+        }
+
+        // the closed forms must not lose precision at the extremes either
+        let positions = Positions::from_list(&[i32::MIN, -1, 0, i32::MAX, i32::MAX]);
+        for y in [i32::MIN, -7, 0, 12345, i32::MAX] {
+            assert_eq!(
+                Quadratic.total(&positions, y),
+                positions.per_crab_total(y, &Quadratic)
+            );
+            assert_eq!(
+                Triangular.total(&positions, y),
+                positions.per_crab_total(y, &Triangular)
+            );
+        }
+    }
+
+    #[test]
+    fn separable_alignment_in_several_dimensions() {
+        use crate::{DistanceMetric, Linear, MeetingPoint, Swarm, Triangular};
+
+        let crabs = vec![[0, 0, 5], [4, 1, 5], [2, 7, 0], [9, 3, 1], [1, 1, 1]];
+        let swarm = Swarm::new(crabs.clone());
         assert_eq!(
-            Some(168),
-            positions.minimum_displacements(metric_part_2)
+            swarm.minimum_separable(&Linear),
+            Ok(MeetingPoint {
+                point: [2, 1, 1],
+                fuel: 30
+            })
         );
+
+        // nowhere in the bounding box needs less fuel
+        let fuel_at = |y: [i32; 3], metric: &dyn DistanceMetric| -> u128 {
+            crabs
+                .iter()
+                .map(|x| (0..3).map(|i| metric.distance(x[i], y[i])).sum::<u128>())
+                .sum()
+        };
+        let least = |metric: &dyn DistanceMetric| {
+            (0..=9)
+                .flat_map(|a| (0..=7).flat_map(move |b| (0..=5).map(move |c| [a, b, c])))
+                .map(|y| fuel_at(y, metric))
+                .min()
+        };
+        let meeting = swarm.minimum_separable(&Linear).unwrap();
+        assert_eq!(Some(meeting.fuel), least(&Linear));
+        assert_eq!(meeting.fuel, fuel_at(meeting.point, &Linear));
+        let meeting = swarm.minimum_separable(&Triangular).unwrap();
+        assert_eq!(Some(meeting.fuel), least(&Triangular));
+        assert_eq!(meeting.fuel, fuel_at(meeting.point, &Triangular));
+    }
+
+    #[test]
+    fn geometric_median() {
+        use crate::Swarm;
+
+        let close =
+            |a: [f64; 2], b: [f64; 2]| (a[0] - b[0]).abs() < 1e-6 && (a[1] - b[1]).abs() < 1e-6;
+
+        // the corners of a square pull equally towards its centre
+        let swarm = Swarm::new(vec![[0, 0], [10, 0], [0, 10], [10, 10]]);
+        let (point, total) = swarm.geometric_median(1e-9).unwrap();
+        assert!(close(point, [5.0, 5.0]));
+        assert!((total - 4.0 * 50f64.sqrt()).abs() < 1e-6);
+
+        // on a line it is the median, which is also one of the crabs
+        let swarm = Swarm::new(vec![[0, 0], [1, 0], [10, 0]]);
+        let (point, total) = swarm.geometric_median(1e-9).unwrap();
+        assert!(close(point, [1.0, 0.0]));
+        assert!((total - 10.0).abs() < 1e-6);
+
+        // and no nearby point is any closer to everyone
+        let swarm = Swarm::new(vec![[0, 0], [7, 1], [3, 9], [12, 4], [5, 5]]);
+        let (point, total) = swarm.geometric_median(1e-9).unwrap();
+        let total_at = |y: [f64; 2]| {
+            swarm
+                .crabs
+                .iter()
+                .map(|x| ((x[0] as f64 - y[0]).powi(2) + (x[1] as f64 - y[1]).powi(2)).sqrt())
+                .sum::<f64>()
+        };
+        for (dx, dy) in [(0.01, 0.0), (-0.01, 0.0), (0.0, 0.01), (0.0, -0.01)] {
+            assert!(total <= total_at([point[0] + dx, point[1] + dy]) + 1e-9);
+        }
+
+        assert_eq!(Swarm::<2>::new(vec![]).geometric_median(1e-9), None);
+    }
+
+    #[test]
+    fn specialised_solvers_match_brute_force() {
+        use crate::{Linear, Positions, Quadratic, Triangular};
+
+        // deterministic pseudo-random crab positions
+        let mut seed = 7_u32;
+        for n in 1..40 {
+            let crabs = (0..n)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    ((seed >> 16) % 200) as i32 - 50
+                })
+                .collect::<Vec<_>>();
+            let positions = Positions::from_list(&crabs);
+
+            let expected = positions.minimum_displacements(&Linear);
+            assert_eq!(positions.minimum_linear(), expected);
+            assert_eq!(positions.minimum_convex(&Linear), expected);
+
+            let expected = positions.minimum_displacements(&Triangular);
+            assert_eq!(positions.minimum_triangular(), expected);
+            assert_eq!(positions.minimum_convex(&Triangular), expected);
+
+            let expected = positions.minimum_displacements(&Quadratic);
+            assert_eq!(positions.minimum_convex(&Quadratic), expected);
+        }
     }
 }
 
+#[allow(dead_code)]
 mod input {
     use std::fs::File;
     use std::io::{self, prelude::*, BufReader};
@@ -1,24 +1,315 @@
 use ndarray::prelude::*;
-use num_traits::Zero;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
 use std::path::Path;
+use union_find::UnionFind;
 
 fn main() {
     let input_file_path = Path::new("../input");
     let data = input::read_as_string(input_file_path).expect("Could not read input file");
-    let heights = input::read_char_array(&data);
+    let heights = input::read_char_array(&data)
+        .unwrap_or_else(|err| panic!("Could not read heights: {}", err));
 
     // Part 1
-    let count = heights.indexed_iter().fold(0, |count, ((i, j), value)| {
-        let view = heights.slice(s![
-            i.saturating_sub(1)..=(i + 1).min(heights.ncols() - 1),
-            j.saturating_sub(1)..=(j + 1).min(heights.nrows() - 1),
-        ]);
-        match Some(value) == view.iter().min() {
-            true => count + value + 1,
-            false => count,
+    let low = low_points(&heights);
+    println!("Low points: {}", low.coordinates.len());
+    println!("Risk Count: {}", low.risk);
+
+    // Part 2
+    let basins = label_regions(&heights, Connectivity::Four, |&height| height != 9);
+    let mut sizes = region_sizes(&basins);
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    println!("Largest basins: {:?}", &sizes[..sizes.len().min(3)]);
+    println!("Basin product: {}", sizes.iter().take(3).product::<usize>());
+
+    // e.g. `cargo run -- basins.png` to draw the basins as an image
+    if let Some(image_path) = std::env::args().nth(1) {
+        let map = render::BasinMap {
+            heights: &heights,
+            labels: &basins,
+            low_points: &low.coordinates,
+        };
+        let mut file = BufWriter::new(File::create(&image_path).expect("could not create image"));
+        match Path::new(&image_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+        {
+            Some("png") => map.write_png(file).expect("could not write PNG"),
+            _ => map.write_ppm(&mut file).expect("could not write PPM"),
         }
-    });
-    println!("Risk Count: {}", count);
+    }
+
+    // Drainage
+    let drainage = Drainage::new(&heights);
+    let mut sizes = drainage.basin_sizes().into_values().collect::<Vec<_>>();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    println!(
+        "Largest drainage basins: {:?}",
+        &sizes[..sizes.len().min(3)]
+    );
+    println!("Saddles: {}", drainage.saddles().len());
+}
+
+/// Cells lower than every orthogonally adjacent cell, and the sum of their
+/// risk levels (one more than their height).
+#[derive(Debug, PartialEq)]
+struct LowPoints {
+    coordinates: Vec<[usize; 2]>,
+    risk: i32,
+}
+
+/// Find the low points of a map of heights of any shape. Cells on the edge
+/// of the map have fewer neighbours to compare against, and a cell level
+/// with any neighbour is not low.
+fn low_points(heights: &Array2<i32>) -> LowPoints {
+    let coordinates = heights
+        .indexed_iter()
+        .filter(|&((i, j), height)| {
+            heights
+                .neighbours([i, j], Connectivity::Four)
+                .all(|(_, neighbour)| height < neighbour)
+        })
+        .map(|((i, j), _)| [i, j])
+        .collect::<Vec<_>>();
+    let risk = coordinates.iter().map(|&index| heights[index] + 1).sum();
+    LowPoints { coordinates, risk }
+}
+
+/// Offsets to the cells sharing an edge with a cell, clockwise from above.
+const ORTHOGONAL: [[isize; 2]; 4] = [[-1, 0], [0, 1], [1, 0], [0, -1]];
+
+/// Offsets to the cells sharing an edge or a corner with a cell, clockwise
+/// from above.
+const SURROUNDING: [[isize; 2]; 8] = [
+    [-1, 0],
+    [-1, 1],
+    [0, 1],
+    [1, 1],
+    [1, 0],
+    [1, -1],
+    [0, -1],
+    [-1, -1],
+];
+
+/// Which cells count as neighbours of a cell.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+enum Connectivity<'a> {
+    /// Cells sharing an edge.
+    Four,
+    /// Cells sharing an edge or a corner.
+    Eight,
+    /// Cells at any chosen offsets, visited in the order given.
+    Custom(&'a [[isize; 2]]),
+}
+
+impl<'a> Connectivity<'a> {
+    fn offsets(self) -> &'a [[isize; 2]] {
+        match self {
+            Connectivity::Four => &ORTHOGONAL,
+            Connectivity::Eight => &SURROUNDING,
+            Connectivity::Custom(offsets) => offsets,
+        }
+    }
+}
+
+/// Index `offset` away from `index`, if that is inside an array of `shape`.
+fn offset_index(
+    index: [usize; 2],
+    offset: [isize; 2],
+    shape: (usize, usize),
+) -> Option<[usize; 2]> {
+    let i = index[0].checked_add_signed(offset[0])?;
+    let j = index[1].checked_add_signed(offset[1])?;
+    (i < shape.0 && j < shape.1).then_some([i, j])
+}
+
+/// Access to the cells around a cell, by signed offsets that may point
+/// beyond the edge of the array.
+trait OffsetAccess<A> {
+    /// Cell `offset` away from `index`, or `None` if outside the array.
+    fn get_offset(&self, index: [usize; 2], offset: [isize; 2]) -> Option<&A>;
+
+    /// Index and value of each neighbour of `index` inside the array, in
+    /// the order of the offsets of `connectivity`.
+    fn neighbours<'a>(
+        &'a self,
+        index: [usize; 2],
+        connectivity: Connectivity<'a>,
+    ) -> impl Iterator<Item = ([usize; 2], &'a A)>
+    where
+        A: 'a;
+}
+
+impl<A> OffsetAccess<A> for Array2<A> {
+    fn get_offset(&self, index: [usize; 2], offset: [isize; 2]) -> Option<&A> {
+        offset_index(index, offset, self.dim()).map(|index| &self[index])
+    }
+
+    fn neighbours<'a>(
+        &'a self,
+        index: [usize; 2],
+        connectivity: Connectivity<'a>,
+    ) -> impl Iterator<Item = ([usize; 2], &'a A)>
+    where
+        A: 'a,
+    {
+        connectivity.offsets().iter().filter_map(move |&offset| {
+            offset_index(index, offset, self.dim()).map(|index| (index, &self[index]))
+        })
+    }
+}
+
+/// Label the connected regions of cells satisfying `in_region`, numbering
+/// them from 0 in the order they are first met, row by row. Cells outside
+/// every region are `None`.
+///
+/// The first pass gives each cell the label of a neighbour already visited,
+/// recording that the labels of all such neighbours name the same region;
+/// the second replaces each label with the one chosen for its region. The
+/// offsets of `connectivity` must come in opposite pairs, as every cell
+/// only looks back at the neighbours before it.
+fn label_regions<A, F>(
+    cells: &Array2<A>,
+    connectivity: Connectivity,
+    in_region: F,
+) -> Array2<Option<usize>>
+where
+    F: Fn(&A) -> bool,
+{
+    let mut labels = Array2::from_elem(cells.dim(), None);
+    let mut regions = UnionFind::new(0);
+    for ((i, j), cell) in cells.indexed_iter() {
+        if !in_region(cell) {
+            continue;
+        }
+        let earlier = cells
+            .neighbours([i, j], connectivity)
+            .filter(|&(index, _)| index < [i, j])
+            .filter_map(|(index, _)| labels[index])
+            .collect::<Vec<usize>>();
+        let label = match earlier.split_first() {
+            None => regions.push(),
+            Some((&first, rest)) => {
+                for &other in rest {
+                    regions.union(first, other);
+                }
+                first
+            }
+        };
+        labels[[i, j]] = Some(label);
+    }
+
+    let mut numbers = BTreeMap::new();
+    labels.map(|label| {
+        label.map(|label| {
+            let root = regions.find(label);
+            let next = numbers.len();
+            *numbers.entry(root).or_insert(next)
+        })
+    })
+}
+
+/// Number of cells with each label, by label.
+fn region_sizes(labels: &Array2<Option<usize>>) -> Vec<usize> {
+    labels
+        .iter()
+        .flatten()
+        .fold(Vec::new(), |mut sizes, &label| {
+            if sizes.len() <= label {
+                sizes.resize(label + 1, 0);
+            }
+            sizes[label] += 1;
+            sizes
+        })
+}
+
+/// Where rain falling on each cell ends up, if water always runs to the
+/// lowest orthogonal neighbour below it. Level neighbours form one flat,
+/// which drains as a whole down the lowest cell around its edge; a flat with
+/// nothing below it is an outlet, named by its first cell in row order. The
+/// outlets are thus the low points, and the floors of any flat-bottomed pits.
+/// Where two or more neighbours tie for lowest, water runs down all of them.
+#[derive(Debug)]
+struct Drainage {
+    /// The outlets each cell drains to, in order; more than one at a saddle.
+    outlets: Array2<Vec<[usize; 2]>>,
+}
+
+impl Drainage {
+    fn new(heights: &Array2<i32>) -> Self {
+        let ncols = heights.ncols();
+        let number = |[i, j]: [usize; 2]| i * ncols + j;
+        let mut level = UnionFind::new(heights.len());
+        for ((i, j), height) in heights.indexed_iter() {
+            for (index, neighbour) in heights.neighbours([i, j], Connectivity::Four) {
+                if neighbour == height {
+                    level.union(number([i, j]), number(index));
+                }
+            }
+        }
+        let mut flats = BTreeMap::<usize, Vec<[usize; 2]>>::new();
+        for ((i, j), _) in heights.indexed_iter() {
+            let root = level.find(number([i, j]));
+            flats.entry(root).or_default().push([i, j]);
+        }
+
+        let mut outlets = Array2::from_elem(heights.dim(), Vec::new());
+        // every flat drains to lower flats, so visit the lowest first
+        let mut flats = flats.into_values().collect::<Vec<_>>();
+        flats.sort_unstable_by_key(|cells| heights[cells[0]]);
+        for cells in flats {
+            let height = heights[cells[0]];
+            let below = || {
+                cells
+                    .iter()
+                    .flat_map(|&index| heights.neighbours(index, Connectivity::Four))
+                    .filter(|&(_, neighbour)| *neighbour < height)
+            };
+            let mut reached = match below().map(|(_, neighbour)| neighbour).min() {
+                None => vec![cells[0]],
+                Some(lowest) => below()
+                    .filter(|(_, neighbour)| *neighbour == lowest)
+                    .flat_map(|(index, _)| outlets[index].clone())
+                    .collect(),
+            };
+            reached.sort_unstable();
+            reached.dedup();
+            for index in cells {
+                outlets[index] = reached.clone();
+            }
+        }
+        Self { outlets }
+    }
+
+    /// The outlet each cell drains to, or `None` for saddles.
+    fn drain_map(&self) -> Array2<Option<[usize; 2]>> {
+        self.outlets.map(|reached| match reached[..] {
+            [outlet] => Some(outlet),
+            _ => None,
+        })
+    }
+
+    /// Number of cells draining to each outlet alone.
+    fn basin_sizes(&self) -> BTreeMap<[usize; 2], usize> {
+        self.drain_map()
+            .iter()
+            .flatten()
+            .fold(BTreeMap::new(), |mut sizes, &outlet| {
+                *sizes.entry(outlet).or_insert(0) += 1;
+                sizes
+            })
+    }
+
+    /// Cells draining to more than one outlet.
+    fn saddles(&self) -> Vec<[usize; 2]> {
+        self.outlets
+            .indexed_iter()
+            .filter(|(_, reached)| reached.len() > 1)
+            .map(|((i, j), _)| [i, j])
+            .collect()
+    }
 }
 
 // neighbours: start from cell above centre point,
@@ -31,44 +322,23 @@ fn main() {
 // true => boundary pixel ▒
 // false => other pixel █
 
-// encode modular phases iterating over adjacent cells in a clockwise direction
-// equivalent to taking the real part of $i^k$ for $k in {0,1,2,3}$.
-fn phase(k: usize) -> i32 {
-    match k % 4 {
-        0 => -1,
-        1 => 0,
-        2 => 1,
-        3 => 0,
-        _ => -1, // can't get here
-    }
-}
-
-// Visit neighbours adjacent to cell at `centre`
-// ...
-fn neighbours(stencil: &Array2<Option<i32>>, centre: &[i32; 2]) -> Vec<i32> {
+// Visit neighbours adjacent to cell at `centre`: each pair of consecutive
+// orthogonal neighbours, then the diagonal between them unless both of the
+// pair are boundary cells (`None`), which would cut the corner off.
+#[allow(dead_code)]
+fn neighbours(stencil: &Array2<Option<i32>>, centre: &[usize; 2]) -> Vec<i32> {
     (0..4)
         .flat_map(|k| {
-            // `ac` stencil-local co-ordinate for cell adjacent to
-            // centre in dimension 0
-            let ac = [
-                (centre[0] + phase(k)) as usize,
-                (centre[1] + phase(k + 1)) as usize,
-            ];
-            // `bc` stencil-local co-ordinate for cell adjacent to
-            // centre in dimension 1
-            let bc = [
-                (centre[0] + phase(k + 1)) as usize,
-                (centre[1] + phase(k + 2)) as usize,
-            ];
-            // diagonal cell of (a,b) -> d
-            let dc = [ac[k % 2], bc[(k + 1) % 2]];
-
-            let a = stencil.get(ac);
-            let b = stencil.get(bc);
+            let (a, b) = (ORTHOGONAL[k], ORTHOGONAL[(k + 1) % 4]);
+            let d = [a[0] + b[0], a[1] + b[1]];
+            let (a, b) = (
+                stencil.get_offset(*centre, a),
+                stencil.get_offset(*centre, b),
+            );
             let d = if a == Some(&None) && b == Some(&None) {
                 None
             } else {
-                stencil.get(dc)
+                stencil.get_offset(*centre, d)
             };
             [a, b, d]
         })
@@ -78,6 +348,297 @@ fn neighbours(stencil: &Array2<Option<i32>>, centre: &[i32; 2]) -> Vec<i32> {
         .collect()
 }
 
+#[allow(dead_code)]
+mod stencil {
+    use ndarray::Array2;
+    use num_traits::Num;
+
+    /// How a kernel sees the cells beyond the edge of the array.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Boundary {
+        /// Only apply the kernel where it fits inside the array, so the
+        /// result is smaller by the kernel's size less one on each axis.
+        Skip,
+        /// Cells beyond the edge are zero.
+        Zero,
+        /// Cells beyond the edge repeat the nearest edge cell.
+        Clamp,
+        /// The array repeats, so leaving one edge enters the opposite one.
+        Wrap,
+        /// The array is mirrored about its edges, edge cells included:
+        /// `c b a | a b c | c b a`.
+        Reflect,
+    }
+
+    impl Boundary {
+        /// Index along an axis of length `len` standing in for `index`, or
+        /// `None` for a cell counted as zero.
+        fn resolve(self, index: isize, len: usize) -> Option<usize> {
+            let len = len as isize;
+            let index = match self {
+                _ if (0..len).contains(&index) => index,
+                Boundary::Skip | Boundary::Zero => return None,
+                Boundary::Clamp => index.clamp(0, len - 1),
+                Boundary::Wrap => index.rem_euclid(len),
+                Boundary::Reflect => match index.rem_euclid(2 * len) {
+                    i if i < len => i,
+                    i => 2 * len - 1 - i,
+                },
+            };
+            Some(index as usize)
+        }
+    }
+
+    /// Apply `kernel` centred on every cell of `array`, summing each weight
+    /// times the cell beneath it. The centre of the kernel is its middle
+    /// cell, or the one above and left of the middle for even sizes. The
+    /// kernel is not flipped, so this is correlation rather than convolution.
+    pub fn apply<A>(array: &Array2<A>, kernel: &Array2<A>, boundary: Boundary) -> Array2<A>
+    where
+        A: Num + Copy,
+    {
+        let (nrows, ncols) = array.dim();
+        let (krows, kcols) = kernel.dim();
+        let centre = ((krows / 2) as isize, (kcols / 2) as isize);
+        let value_at =
+            |i: isize, j: isize| match (boundary.resolve(i, nrows), boundary.resolve(j, ncols)) {
+                (Some(i), Some(j)) => array[(i, j)],
+                _ => A::zero(),
+            };
+        let apply_at = |i: isize, j: isize| {
+            kernel
+                .indexed_iter()
+                .fold(A::zero(), |sum, ((ki, kj), &weight)| {
+                    let di = ki as isize - centre.0;
+                    let dj = kj as isize - centre.1;
+                    sum + weight * value_at(i + di, j + dj)
+                })
+        };
+        match boundary {
+            Boundary::Skip => {
+                let shape = (
+                    (nrows + 1).saturating_sub(krows),
+                    (ncols + 1).saturating_sub(kcols),
+                );
+                Array2::from_shape_fn(shape, |(i, j)| {
+                    apply_at(i as isize + centre.0, j as isize + centre.1)
+                })
+            }
+            _ => Array2::from_shape_fn((nrows, ncols), |(i, j)| apply_at(i as isize, j as isize)),
+        }
+    }
+
+    /// Discrete Laplacian over the orthogonal neighbours: positive where a
+    /// cell is lower than the average of its neighbours.
+    pub fn laplacian() -> Array2<i32> {
+        ndarray::arr2(&[[0, 1, 0], [1, -4, 1], [0, 1, 0]])
+    }
+
+    /// Twice the central-difference gradient down the rows and along the columns.
+    pub fn gradients() -> (Array2<i32>, Array2<i32>) {
+        (
+            ndarray::arr2(&[[-1], [0], [1]]),
+            ndarray::arr2(&[[-1, 0, 1]]),
+        )
+    }
+}
+
+/// Pictures of basins, to check labelling by eye.
+#[allow(dead_code)]
+mod render {
+    use ndarray::Array2;
+    use std::fmt::Write as _;
+    use std::io::{self, Write};
+
+    /// Heights labelled by basin, with the low points at the bottom of them.
+    /// Cells of height 9 are ridges between basins; other unlabelled cells
+    /// are shown as such, so that labelling gaps stand out.
+    pub struct BasinMap<'a> {
+        pub heights: &'a Array2<i32>,
+        pub labels: &'a Array2<Option<usize>>,
+        pub low_points: &'a [[usize; 2]],
+    }
+
+    enum Cell {
+        Ridge,
+        Unlabelled,
+        LowPoint(usize),
+        Basin(usize),
+    }
+
+    const RIDGE: [u8; 3] = [48, 48, 48];
+    const UNLABELLED: [u8; 3] = [0, 0, 0];
+    const LOW_POINT: [u8; 3] = [255, 255, 255];
+
+    /// A colour for each label, stepping round the hue circle by the golden
+    /// angle so that neighbouring labels are far apart.
+    pub fn colour(label: usize) -> [u8; 3] {
+        let hue = (label as f64 * 0.618_033_988_75).fract() * 6.0;
+        let (saturation, value) = (0.65, 0.95);
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as usize {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = value - chroma;
+        [r, g, b].map(|c| ((c + m) * 255.0).round() as u8)
+    }
+
+    impl BasinMap<'_> {
+        fn cell(&self, index: [usize; 2]) -> Cell {
+            match (self.heights[index], self.labels[index]) {
+                (9, _) => Cell::Ridge,
+                (_, None) => Cell::Unlabelled,
+                (_, Some(label)) if self.low_points.contains(&index) => Cell::LowPoint(label),
+                (_, Some(label)) => Cell::Basin(label),
+            }
+        }
+
+        /// The map as text for a terminal: each height on its basin's colour,
+        /// low points in bold white, ridges as grey blocks and unlabelled
+        /// cells as `?`.
+        pub fn to_ansi(&self) -> String {
+            let mut text = String::new();
+            for (i, heights) in self.heights.outer_iter().enumerate() {
+                for (j, height) in heights.iter().enumerate() {
+                    let _ = match self.cell([i, j]) {
+                        Cell::Ridge => write!(text, "\x1b[0;38;5;240m█"),
+                        Cell::Unlabelled => write!(text, "\x1b[0m?"),
+                        Cell::LowPoint(label) => {
+                            let [r, g, b] = colour(label);
+                            write!(text, "\x1b[1;97;48;2;{};{};{}m{}", r, g, b, height)
+                        }
+                        Cell::Basin(label) => {
+                            let [r, g, b] = colour(label);
+                            write!(text, "\x1b[22;30;48;2;{};{};{}m{}", r, g, b, height)
+                        }
+                    };
+                }
+                text.push_str("\x1b[0m\n");
+            }
+            text
+        }
+
+        /// Red, green and blue for each cell, row by row.
+        pub fn to_rgb(&self) -> Vec<u8> {
+            self.heights
+                .indexed_iter()
+                .flat_map(|((i, j), _)| match self.cell([i, j]) {
+                    Cell::Ridge => RIDGE,
+                    Cell::Unlabelled => UNLABELLED,
+                    Cell::LowPoint(_) => LOW_POINT,
+                    Cell::Basin(label) => colour(label),
+                })
+                .collect()
+        }
+
+        pub fn write_ppm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+            let (nrows, ncols) = self.heights.dim();
+            write!(writer, "P6\n{} {}\n255\n", ncols, nrows)?;
+            writer.write_all(&self.to_rgb())
+        }
+
+        pub fn write_png<W: Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+            let (nrows, ncols) = self.heights.dim();
+            let mut encoder = png::Encoder::new(writer, ncols as u32, nrows as u32);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.write_header()?.write_image_data(&self.to_rgb())
+        }
+    }
+}
+
+/// Disjoint sets, for merging labels that turn out to name the same thing.
+/// Needs nothing but `std`, so can be copied into any day as it stands.
+#[allow(dead_code)]
+mod union_find {
+    /// Disjoint sets of the elements `0..n`, each set named by one of its
+    /// elements, the root. Finding a root points every element passed on the
+    /// way straight at it, and joining two sets hangs the smaller from the
+    /// larger, so both take close to constant time.
+    #[derive(Debug, Clone)]
+    pub struct UnionFind {
+        parent: Vec<usize>,
+        size: Vec<usize>,
+    }
+
+    impl UnionFind {
+        /// Elements `0..n`, each in a set of its own.
+        pub fn new(n: usize) -> Self {
+            Self {
+                parent: (0..n).collect(),
+                size: vec![1; n],
+            }
+        }
+
+        /// Add an element in a set of its own, returning it.
+        pub fn push(&mut self) -> usize {
+            let element = self.parent.len();
+            self.parent.push(element);
+            self.size.push(1);
+            element
+        }
+
+        pub fn elements(&self) -> usize {
+            self.parent.len()
+        }
+
+        /// Root of the set containing `element`.
+        pub fn find(&mut self, element: usize) -> usize {
+            let mut root = element;
+            while self.parent[root] != root {
+                root = self.parent[root];
+            }
+            let mut current = element;
+            while self.parent[current] != root {
+                let next = self.parent[current];
+                self.parent[current] = root;
+                current = next;
+            }
+            root
+        }
+
+        /// Join the sets containing `a` and `b`, returning the root of the
+        /// joined set.
+        pub fn union(&mut self, a: usize, b: usize) -> usize {
+            let (a, b) = (self.find(a), self.find(b));
+            if a == b {
+                return a;
+            }
+            let (large, small) = match self.size[a] >= self.size[b] {
+                true => (a, b),
+                false => (b, a),
+            };
+            self.parent[small] = large;
+            self.size[large] += self.size[small];
+            large
+        }
+
+        pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+            self.find(a) == self.find(b)
+        }
+
+        /// Number of elements in the set containing `element`.
+        pub fn set_size(&mut self, element: usize) -> usize {
+            let root = self.find(element);
+            self.size[root]
+        }
+
+        /// Number of disjoint sets.
+        pub fn sets(&self) -> usize {
+            (0..self.parent.len())
+                .filter(|&element| self.parent[element] == element)
+                .count()
+        }
+    }
+}
+
+#[cfg(test)]
 mod tests {
 
     #[test]
@@ -99,7 +660,6 @@ mod tests {
     #[test]
     fn string_of_numerical_chars_to_ndarray() {
         use crate::input;
-        use ndarray::prelude::*;
 
         let chars = "\
         986545679234\n\
@@ -114,11 +674,66 @@ mod tests {
         878656989349\n\
         ";
 
-        let ary = input::read_char_array(chars);
-        println!("{:?}", ary);
+        let ary = input::read_char_array(chars).unwrap();
+        assert_eq!(ary.dim(), (10, 12));
+        assert_eq!(
+            ary.row(0).to_vec(),
+            vec![9, 8, 6, 5, 4, 5, 6, 7, 9, 2, 3, 4]
+        );
+        assert_eq!(ary[(9, 11)], 9);
+    }
+
+    #[test]
+    fn read_grids_of_any_alphabet() {
+        use crate::input::{read_grid, GridError};
+        use ndarray::prelude::*;
+
+        let walls = read_grid("#..\n.#.\n", |ch| match ch {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        });
+        assert_eq!(
+            walls,
+            Ok(arr2(&[[true, false, false], [false, true, false]]))
+        );
+
+        let letters = read_grid("Sab\nzzE", |ch| ch.is_ascii_alphabetic().then_some(ch));
+        assert_eq!(letters.unwrap()[(1, 2)], 'E');
+
+        let ragged = read_grid("123\n45\n678\n", crate::input::digit);
+        assert_eq!(
+            ragged,
+            Err(GridError::Ragged {
+                line: 2,
+                width: 2,
+                expected: 3
+            })
+        );
+        assert_eq!(
+            ragged.unwrap_err().to_string(),
+            "line 2 has 2 characters, expected 3"
+        );
+
+        let unmapped = read_grid("123\n4x6\n", crate::input::digit);
+        assert_eq!(
+            unmapped,
+            Err(GridError::Unmapped {
+                line: 2,
+                column: 2,
+                ch: 'x'
+            })
+        );
+        assert_eq!(
+            unmapped.unwrap_err().to_string(),
+            "unexpected 'x' at line 2, column 2"
+        );
+
+        assert_eq!(read_grid("", crate::input::digit), Err(GridError::Empty));
     }
     #[test]
     fn example_part_1() {
+        use crate::{low_points, LowPoints};
         use ndarray::prelude::*;
 
         let heights = arr2(&[
@@ -129,48 +744,36 @@ mod tests {
             [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
         ]);
 
-        // println!("{:?}", heights);
-        // println!("{:?} == {}, {}", heights.shape(), heights.nrows(), heights.ncols());
-        // println!("{:?}", heights.get((0,0)));
-        // println!("{:?}", heights.get((3,3)));
-        // let mut count = 0_usize;
-        let i_max: usize = heights.ncols();
-        let j_max: usize = heights.nrows();
-        for j in 0..j_max {
-            for i in 0..i_max {
-                let view = heights.slice(s![
-                    j.saturating_sub(1)..=(j + 1).min(j_max - 1),
-                    i.saturating_sub(1)..=(i + 1).min(i_max - 1),
-                ]);
-                let e = heights.get((j, i));
-                let minimum = view.iter().min();
-                match e == minimum {
-                    true => println!("{:?} in:\n{:?}\n", e, view),
-                    false => (),
-                }
-
-                // let mask_template = arr2(&[[0, 1, 0], [1, 1, 1], [0, 1, 0]]);
-                // let mask = mask_template.slice(
-                //     s![
-                //         (-1) ..=
-                //     ]
-                // )
-
-                // let kernel = mask * view;
-                // println!("{:?}", kernel);
-
-                // count += 1;
-                // println!(
-                //     "count: {}, value: {:?} ",
-                //     count,
-                //     heights.get((j,i))
-                // );
+        assert_eq!(
+            low_points(&heights),
+            LowPoints {
+                coordinates: vec![[0, 1], [0, 9], [2, 2], [4, 6]],
+                risk: 15,
             }
-        }
+        );
+    }
+
+    #[test]
+    fn low_points_ignore_diagonals_and_plateaus() {
+        use crate::low_points;
+        use ndarray::prelude::*;
+
+        // a diagonal neighbour may be lower; level neighbours are not lower
+        let heights = arr2(&[[5, 3, 5, 5, 5, 5, 9], [3, 9, 4, 5, 1, 1, 9]]);
+        let low = low_points(&heights);
+        assert_eq!(low.coordinates, vec![[0, 1], [1, 0], [1, 2]]);
+        assert_eq!(low.risk, 13);
+
+        let column = arr2(&[[4], [2], [3], [0]]);
+        assert_eq!(low_points(&column).coordinates, vec![[1, 0], [3, 0]]);
+
+        let flat = Array2::<i32>::zeros((3, 5));
+        assert!(low_points(&flat).coordinates.is_empty());
     }
 
     #[test]
     fn example_part_2() {
+        use crate::{label_regions, region_sizes, Connectivity};
         use ndarray::prelude::*;
 
         let heights = arr2(&[
@@ -181,163 +784,76 @@ mod tests {
             [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
         ]);
 
-        println!("{:?}", heights);
-        let boundary_value = 9;
-        // let boundaries = heights.mapv(|v| (v == boundary_value) as usize);
-        // println!("{:?}", boundaries);
-
-        let mut regions = heights.mapv(|v| match v == boundary_value {
-            true => None,
-            false => Some(0),
-        });
-        println!("{:?}", regions);
-
-        // cases:
-        //  - all adjacent cells are boundary cells:
-        //    cell is a singleton => increment counter and write to cell
-        //  - cell is surrounded by some mix of boundary/non-boundary cells:
-        //    check if any 'neighbouring cells' are non-boundary have marked value,
-        //      if so, mark cell with the max. of this value (there should only be one)
-        //      if no such value exists, increment `counter` and write this updated value to the cell.
-        //  where 'neighbouring cells' are
-        //        { [i, j-1], [i-1, j], [i, j+1], [] }  if any such exist and are not boundary cells
-        let mut counter: usize = 1;
-
-        for i in 0..regions.nrows() {
-            for j in 0..regions.ncols() {
-                let cell_value = regions.get((i, j)).unwrap();
-                match cell_value {
-                    None => (),
-                    // get adjacent cells from `regions` and check if any have already been filled
-                    Some(_) => {
-                        let prev_j = match j == 0 {
-                            true => None,
-                            false => unsafe { regions.uget((i, j - 1)).as_ref() },
-                        };
-                        let next_j = match j < regions.ncols() - 2 {
-                            true => unsafe { regions.uget((i, j + 1)).as_ref() },
-                            false => None,
-                        };
-                        let prev_i = match i == 0 {
-                            true => None,
-                            false => unsafe { regions.uget((i - 1, j)).as_ref() },
-                        };
-                        let next_i = match i < regions.nrows() - 2 {
-                            true => unsafe { regions.uget((i + 1, j)).as_ref() },
-                            false => None,
-                        };
-
-                        let adjacents = [prev_j, prev_i, next_j, next_i]
-                            .iter()
-                            .copied()
-                            .to_owned()
-                            .collect::<Vec<_>>();
-
-                        match adjacents.iter().copied().flatten().max() {
-                            // all adjacents are None => boundary cells
-                            None => {
-                                counter += 1;
-                                let cell = unsafe { regions.uget_mut((i, j)) };
-                                *cell = Some(counter);
-                            }
-                            // one or more of the adjacent cells is not a boundary cell
-                            Some(&region_index) => {
-                                // if the max value here is 0, all non-boundary adjacent cells
-                                // are untouched, so need a new region index value
-                                if region_index == 0 {
-                                    counter += 1;
-                                    let cell = unsafe { regions.uget_mut((i, j)) };
-                                    *cell = Some(counter);
-                                // otherwise one of the adjacent cells belongs to a region
-                                // which has already been given an index value. Copy it to
-                                // this cell
-                                } else {
-                                    let cell = unsafe { regions.uget_mut((i, j)) };
-                                    *cell = Some(region_index);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        let regions_unwrapped = regions.mapv(|v| v.unwrap_or(0));
-        println!("{:?}", regions);
-        println!("{:?}", regions_unwrapped);
+        let basins = label_regions(&heights, Connectivity::Four, |&height| height != 9);
+        let expected = arr2(&[
+            [0, 0, 9, 9, 9, 1, 1, 1, 1, 1],
+            [0, 9, 2, 2, 2, 9, 1, 9, 1, 1],
+            [9, 2, 2, 2, 2, 2, 9, 3, 9, 1],
+            [2, 2, 2, 2, 2, 9, 3, 3, 3, 9],
+            [9, 2, 9, 9, 9, 3, 3, 3, 3, 3],
+        ])
+        .mapv(|label| (label != 9).then_some(label));
+        assert_eq!(basins, expected);
+        assert_eq!(region_sizes(&basins), vec![3, 9, 14, 9]);
     }
 
     #[test]
-    fn stencil() {
+    fn labels_merge_when_regions_meet_later() {
+        use crate::{label_regions, region_sizes, Connectivity};
         use ndarray::prelude::*;
-        let heights = arr2(&[
-            [2, 1, 9, 9, 9, 4, 3, 2, 1, 0],
-            [3, 9, 8, 7, 8, 9, 4, 9, 2, 1],
-            [9, 8, 5, 6, 7, 8, 9, 8, 9, 2],
-            [8, 7, 6, 7, 8, 9, 6, 7, 8, 9],
-            [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
+
+        // a U shape is first met as two separate arms, joined only on the last row
+        let cells = arr2(&[
+            [1, 0, 1, 0, 1],
+            [1, 0, 1, 0, 1],
+            [1, 1, 1, 0, 1],
+            [0, 0, 0, 1, 1],
         ]);
+        let regions = label_regions(&cells, Connectivity::Four, |&cell| cell == 1);
+        assert_eq!(
+            regions.row(0).to_vec(),
+            vec![Some(0), None, Some(0), None, Some(1)]
+        );
+        assert_eq!(region_sizes(&regions), vec![7, 5]);
 
-        let boundary_value = 9;
-        let mut regions = heights.mapv(|v| match v == boundary_value {
-            true => None,
-            false => Some(0),
-        });
-        println!("{:?}", regions);
-
-        let mut counter = 1;
-
-        for i in 0..regions.nrows() {
-            for j in 0..regions.ncols() {
-                let cell_value = regions.get((i, j)).unwrap();
-                // Check if this is a boundary cell
-                match cell_value {
-                    None => (),
-                    Some(_) => {
-                        let view = regions
-                            .slice(s![
-                                i.saturating_sub(1)..regions.nrows().min(i + 2),
-                                j.saturating_sub(1)..regions.ncols().min(j + 2),
-                            ])
-                            .into_owned();
-                        let neighbour = view.iter().flatten().max();
-                        match neighbour {
-                            // all adjacents are None => boundary cells
-                            None => {
-                                counter += 1;
-                                let cell = regions.get_mut((i, j)).unwrap();
-                                *cell = Some(counter);
-                                println!("GOT HERE!");
-                            }
-                            // one or more of the adjacent cells is not a boundary cell
-                            Some(&region_index) => {
-                                // if the max value here is 0, all non-boundary adjacent cells
-                                // are untouched, so need a new region index value
-                                if region_index == 0 {
-                                    counter += 1;
-                                    let cell = regions.get_mut((i, j)).unwrap();
-                                    *cell = Some(counter);
-                                // otherwise one of the adjacent cells belongs to a region
-                                // which has already been given an index value. Copy it to
-                                // this cell
-                                } else {
-                                    let cell = regions.get_mut((i, j)).unwrap();
-                                    *cell = Some(region_index);
-                                }
-                            }
-                        }
-                        println!("({}, {})", i, j);
-                        println!("view: {:?}", view);
-                        println!(" = {:?}", view.get((i, j)));
-                        // println!("adjacents: {:?}", adjacents.iter().flatten().collect::<Vec<_>>());
-                        // println!("diagonals: {:?}", diagonals.iter().flatten().collect::<Vec<_>>());
-                        println!("'neighbour' {:?}", neighbour);
-                    }
-                }
+        // corners join regions only with diagonal connectivity
+        let chequered = arr2(&[[1, 0, 1], [0, 1, 0]]);
+        let four = label_regions(&chequered, Connectivity::Four, |&cell| cell == 1);
+        let eight = label_regions(&chequered, Connectivity::Eight, |&cell| cell == 1);
+        assert_eq!(region_sizes(&four), vec![1, 1, 1]);
+        assert_eq!(region_sizes(&eight), vec![3]);
+    }
+
+    #[test]
+    fn union_find() {
+        use crate::union_find::UnionFind;
+
+        let mut sets = UnionFind::new(6);
+        assert_eq!(sets.sets(), 6);
+        sets.union(0, 1);
+        sets.union(2, 3);
+        sets.union(1, 3);
+        assert!(sets.same_set(0, 2));
+        assert!(!sets.same_set(0, 4));
+        assert_eq!(sets.set_size(3), 4);
+        assert_eq!(sets.sets(), 3);
+
+        let root = sets.find(0);
+        assert_eq!(sets.union(3, 0), root);
+        assert_eq!(sets.push(), 6);
+        assert_eq!(sets.elements(), 7);
+        assert_eq!(sets.set_size(6), 1);
+
+        let mut chain = UnionFind::new(0);
+        for k in 0..100 {
+            chain.push();
+            if k > 0 {
+                chain.union(k, k - 1);
             }
         }
-        println!("{}", heights);
-        println!("{:?}", regions);
+        assert_eq!(chain.sets(), 1);
+        assert_eq!(chain.set_size(0), 100);
+        assert_eq!(chain.find(99), chain.find(0));
     }
 
     #[test]
@@ -357,158 +873,254 @@ mod tests {
     }
 
     #[test]
-    fn stencil_bounds() {
+    fn offset_access() {
+        use crate::{Connectivity, OffsetAccess};
         use ndarray::prelude::*;
-        use ndarray::{IntoDimension, NdIndex};
-        use ndarray::{Ix, IxDyn, Ixs, SliceInfo, SliceInfoElem};
 
-        let ux: [Ix; 2] = [2, 3];
-        let ix: [Ixs; 2] = [-2, -3];
-        let u1 = Ix1(2);
+        let grid = arr2(&[[1, 2, 3, 4], [5, 6, 7, 8], [9, 10, 11, 12]]);
+        assert_eq!(grid.get_offset([1, 1], [-1, -1]), Some(&1));
+        assert_eq!(grid.get_offset([1, 1], [1, 2]), Some(&12));
+        assert_eq!(grid.get_offset([0, 0], [-1, 0]), None);
+        assert_eq!(grid.get_offset([0, 3], [0, 1]), None);
+        assert_eq!(grid.get_offset([2, 0], [1, 0]), None);
+        assert_eq!(grid.get_offset([0, 0], [isize::MIN, 0]), None);
 
-        let sl = SliceInfoElem::Slice {
-            start: -2,
-            end: Some(2),
-            step: 1,
+        let values = |index, connectivity| {
+            grid.neighbours(index, connectivity)
+                .map(|(_, &value)| value)
+                .collect::<Vec<_>>()
         };
+        assert_eq!(values([1, 1], Connectivity::Four), vec![2, 7, 10, 5]);
+        assert_eq!(
+            values([1, 1], Connectivity::Eight),
+            vec![2, 3, 7, 11, 10, 9, 5, 1]
+        );
+        assert_eq!(values([0, 0], Connectivity::Four), vec![2, 5]);
+        assert_eq!(values([2, 3], Connectivity::Eight), vec![8, 11, 7]);
 
-        fn get_unbounded<'a, S, D>(
-            a: &'a Array<S, D>,
-            index: &Array<Ix, D>,
-            offset: &Array<Ixs, D>,
-        ) -> Option<&'a S>
-        where
-            D: Dimension,
-            Array<Ix, D>: NdIndex<D>,
-        {
-            if index.ndim() != offset.ndim() {
-                panic!(
-                    "index of {} dimensions does not match offset of {}",
-                    index.ndim(),
-                    offset.len()
-                );
-                todo!("this should be handled at the type level");
-            } else {
-                // check if the index with offset is outside the bounds of array `a` as:
-                // (a) less than zero on any axis
-                let is_outside_zero_bound =
-                    index.iter().zip(offset).any(|(i, k)| (*i as Ixs + k) < 0);
-                // (b) index with offset is greater than length of any respective axis
-                let is_outside_max_bound = index
-                    .iter()
-                    .zip(offset)
-                    .zip(a.shape())
-                    .any(|((i, k), l)| (*i as Ixs + k) > (*l as isize));
-                if is_outside_zero_bound || is_outside_max_bound {
-                    None
-                } else {
-                    let offset_index = (index + offset.map(|x| *x as Ix)).into_raw_vec();
-                    a.get(index)
-                }
+        let knight = [
+            [-2, -1],
+            [-2, 1],
+            [-1, 2],
+            [1, 2],
+            [2, 1],
+            [2, -1],
+            [1, -2],
+            [-1, -2],
+        ];
+        let moves = grid
+            .neighbours([0, 0], Connectivity::Custom(&knight))
+            .collect::<Vec<_>>();
+        assert_eq!(moves, vec![([1, 2], &7), ([2, 1], &10)]);
+    }
+
+    #[test]
+    fn render_basins() {
+        use crate::low_points;
+        use crate::render::{colour, BasinMap};
+        use ndarray::prelude::*;
+
+        let heights = arr2(&[
+            [2, 1, 9, 9, 9, 4, 3, 2, 1, 0],
+            [3, 9, 8, 7, 8, 9, 4, 9, 2, 1],
+            [9, 8, 5, 6, 7, 8, 9, 8, 9, 2],
+            [8, 7, 6, 7, 8, 9, 6, 7, 8, 9],
+            [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
+        ]);
+        let labels = arr2(&[
+            [1, 1, 0, 0, 0, 2, 2, 2, 2, 2],
+            [1, 0, 3, 3, 3, 0, 2, 0, 2, 2],
+            [0, 3, 3, 3, 3, 3, 0, 4, 0, 2],
+            [3, 3, 3, 3, 3, 0, 4, 4, 4, 0],
+            [0, 3, 0, 0, 0, 4, 4, 4, 4, 4],
+        ])
+        .mapv(|label| (label > 0).then_some(label));
+        let low = low_points(&heights);
+        let map = BasinMap {
+            heights: &heights,
+            labels: &labels,
+            low_points: &low.coordinates,
+        };
+
+        let colours = (1..=4).map(colour).collect::<Vec<_>>();
+        for (k, a) in colours.iter().enumerate() {
+            assert!(colours[k + 1..].iter().all(|b| a != b));
+        }
+
+        // without the escape codes the text is the map, with 9s as blocks
+        let text = map.to_ansi();
+        let mut plain = String::new();
+        let mut escaped = false;
+        for ch in text.chars() {
+            match ch {
+                '\x1b' => escaped = true,
+                'm' if escaped => escaped = false,
+                _ if escaped => (),
+                _ => plain.push(ch),
             }
         }
+        assert_eq!(plain.lines().next(), Some("21███43210"));
+        assert_eq!(plain.lines().count(), 5);
+        assert!(text.contains("\x1b[1;97;48;2;"));
 
-        let array_1d = arr1(&[2, 1, 9, 9, 9, 4, 3, 2, 1, 0]);
+        let rgb = map.to_rgb();
+        let pixel = |i: usize, j: usize| &rgb[3 * (i * 10 + j)..3 * (i * 10 + j + 1)];
+        assert_eq!(pixel(0, 0), colour(1));
+        assert_eq!(pixel(0, 1), [255, 255, 255]);
+        assert_eq!(pixel(0, 2), [48, 48, 48]);
+        assert_eq!(pixel(4, 9), colour(4));
 
-        let array_2d = arr2(&[
+        let mut ppm = Vec::new();
+        map.write_ppm(&mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n10 5\n255\n"));
+        assert_eq!(ppm.len(), b"P6\n10 5\n255\n".len() + 150);
+
+        let mut png = Vec::new();
+        map.write_png(&mut png).unwrap();
+        let reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (10, 5));
+    }
+
+    #[test]
+    fn drainage() {
+        use crate::{low_points, Drainage};
+        use ndarray::prelude::*;
+
+        let heights = arr2(&[
             [2, 1, 9, 9, 9, 4, 3, 2, 1, 0],
             [3, 9, 8, 7, 8, 9, 4, 9, 2, 1],
             [9, 8, 5, 6, 7, 8, 9, 8, 9, 2],
             [8, 7, 6, 7, 8, 9, 6, 7, 8, 9],
             [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
         ]);
+        let drainage = Drainage::new(&heights);
+        let sizes = drainage.basin_sizes();
+        let outlets = sizes.keys().copied().collect::<Vec<_>>();
+        assert_eq!(outlets, low_points(&heights).coordinates);
+        // every cell, ridges included, drains somewhere
+        assert_eq!(sizes.values().sum::<usize>() + drainage.saddles().len(), 50);
+        assert_eq!(drainage.drain_map()[[2, 4]], Some([2, 2]));
+        // the ridge along the top drains as one flat to its lowest edge
+        assert_eq!(drainage.drain_map()[[0, 4]], Some([0, 1]));
 
-        let indy = [2, 100];
+        // the peak between two pits drains into both
+        let ridge = arr2(&[[1, 5, 1]]);
+        let drainage = Drainage::new(&ridge);
+        assert_eq!(drainage.saddles(), vec![[0, 1]]);
+        assert_eq!(
+            drainage.drain_map(),
+            arr2(&[[Some([0, 0]), None, Some([0, 2])]])
+        );
+        assert_eq!(drainage.basin_sizes().values().sum::<usize>(), 2);
 
-        let got = array_2d.get(indy);
-        let maybegot = get_unbounded(&array_2d, &arr2(&[0, 1]), &arr2(&[-2, -2]));
+        // tied paths that meet again lead to one outlet
+        let slope = arr2(&[[0, 1, 2], [1, 2, 3]]);
+        let drainage = Drainage::new(&slope);
+        assert!(drainage.saddles().is_empty());
+        assert_eq!(
+            drainage.basin_sizes().into_iter().collect::<Vec<_>>(),
+            vec![([0, 0], 6)]
+        );
 
-        dbg!(array_2d.dim());
-        dbg!(got);
-        // dbg!(get_unbounded(&array_1d, arr1(&[1]), -2));
+        // a flat drains as one down the lowest cell around it
+        let shelf = arr2(&[[1, 5, 5, 0], [6, 5, 7, 8]]);
+        let drainage = Drainage::new(&shelf);
+        assert_eq!(
+            drainage.basin_sizes().into_iter().collect::<Vec<_>>(),
+            vec![([0, 0], 2), ([0, 3], 6)]
+        );
+        assert!(drainage.saddles().is_empty());
 
-        // a stencil is composed of a slice (c.f. `s![]` documentation..?)
-        // and a mask... ok, lets just work on the slice element
+        // a flat with nothing below it is a single outlet
+        let pit = arr2(&[[3, 3], [4, 5]]);
+        let drainage = Drainage::new(&pit);
+        assert_eq!(
+            drainage.basin_sizes().into_iter().collect::<Vec<_>>(),
+            vec![([0, 0], 4)]
+        );
+        assert!(drainage.saddles().is_empty());
+    }
 
-        // e.g.
-        // (i-2..i+2)
+    #[test]
+    fn drainage_outlets_are_low_points() {
+        use crate::{input, low_points, Drainage};
+        use std::path::Path;
 
-        fn get_unsat<A, D>(array: &Array<A, D>, k: Ix, dk: Ix) -> Option<usize>
-        where
-            D: Dimension,
-        {
-            None
-            // match (k as isize + dk) > 0 {
-            //     true => Some(array.get( k + dk )),
-            //     false => None,
-            // }
-        }
+        let data = input::read_as_string(Path::new("../input")).unwrap();
+        let heights = input::read_char_array(&data).unwrap();
+        let drainage = Drainage::new(&heights);
+        let outlets = drainage.basin_sizes().into_keys().collect::<Vec<_>>();
+        assert_eq!(outlets, low_points(&heights).coordinates);
+    }
 
-        for i in 0..array_1d.len() - 1 {
-            // let st = (i-2..i+2).map(|k| k )
-            // print!("{}: {:?}, ", i-1, array_1d.get(unsat(i,-1)));
-            print!("{}: {:?}, ", i, array_1d.get(i));
-            print!("{}: {:?}, ", i + 1, array_1d.get(i + 1));
-            // dbg!(i, array_1d.slice(s![i-1..i+1]));
-            // println!();
-        }
+    #[test]
+    fn stencil_boundaries() {
+        use crate::stencil::{apply, Boundary};
+        use ndarray::prelude::*;
+
+        let row = arr2(&[[1, 2, 3]]);
+        let window = arr2(&[[1, 1, 1]]);
+        assert_eq!(apply(&row, &window, Boundary::Skip), arr2(&[[6]]));
+        assert_eq!(apply(&row, &window, Boundary::Zero), arr2(&[[3, 6, 5]]));
+        assert_eq!(apply(&row, &window, Boundary::Clamp), arr2(&[[4, 6, 8]]));
+        assert_eq!(apply(&row, &window, Boundary::Wrap), arr2(&[[6, 6, 6]]));
+        assert_eq!(apply(&row, &window, Boundary::Reflect), arr2(&[[4, 6, 8]]));
 
-        println!("1d: {:?}", array_1d.dim());
-        // println!("2d: {:?}", array_2d.dim());
+        // reaching two cells over tells clamping and reflecting apart
+        let wide = arr2(&[[1, 1, 1, 1, 1]]);
+        assert_eq!(apply(&row, &wide, Boundary::Clamp), arr2(&[[8, 10, 12]]));
+        assert_eq!(apply(&row, &wide, Boundary::Reflect), arr2(&[[9, 10, 11]]));
+        assert_eq!(apply(&row, &wide, Boundary::Wrap), arr2(&[[11, 10, 9]]));
+        assert_eq!(apply(&row, &wide, Boundary::Skip).dim(), (1, 0));
+
+        // a kernel applies down columns just as along rows
+        let column = row.t().to_owned();
+        assert_eq!(
+            apply(&column, &window.t().to_owned(), Boundary::Zero),
+            arr2(&[[3], [6], [5]])
+        );
     }
 
     #[test]
-    fn laplace() {
+    fn laplacian_and_gradients_of_heights() {
+        use crate::low_points;
+        use crate::stencil::{apply, gradients, laplacian, Boundary};
         use ndarray::prelude::*;
 
         let heights = arr2(&[
             [2, 1, 9, 9, 9, 4, 3, 2, 1, 0],
-            // [3, 9, 8, 7, 8, 9, 4, 9, 2, 1],
-            // [9, 8, 5, 6, 7, 8, 9, 8, 9, 2],
-            // [8, 7, 6, 7, 8, 9, 6, 7, 8, 9],
-            // [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
+            [3, 9, 8, 7, 8, 9, 4, 9, 2, 1],
+            [9, 8, 5, 6, 7, 8, 9, 8, 9, 2],
+            [8, 7, 6, 7, 8, 9, 6, 7, 8, 9],
+            [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
         ]);
 
-        for ((i, j), v) in heights.indexed_iter() {
-            println!("({},{})", i, j);
-            let mask = arr2(&[
-                [-1.0, 1.0, -1.0]
-                // [ 0., -1.,  0.],
-                // [-1.,  4., -1.],
-                // [ 0., -1.,  0.],
-            ]);
-
-            let h_i = mask.nrows() / 2; // kernel width, (or radius iff mask is square / symmetrical)
-            let h_j = mask.ncols() / 2; // kernel width, (or radius iff mask is square / symmetrical)
-            let ghost_below_i = h_i.saturating_sub(i); // effectively max(0, i - k_r)
-            let ghost_below_j = h_j.saturating_sub(j); // "" for j
-            let ghost_above_i = (i + h_i + 1).saturating_sub(heights.nrows()); // effectively max(0, i + k_r - nrows)
-            let ghost_above_j = (j + h_j + 1).saturating_sub(heights.ncols()); // effectively max(0, i + k_r - ncols)
-
-            let slice = s![
-                i.saturating_sub(h_i + ghost_below_i)..=(i + h_i - ghost_above_i),
-                j.saturating_sub(h_j + ghost_below_j)..=(j + h_j - ghost_above_j),
-            ];
-
-            let mask_slice = s![
-                ghost_below_i..(mask.nrows() - ghost_above_i),
-                ghost_below_j..(mask.ncols() - ghost_above_j),
-            ];
-
-            let view = heights.slice(slice).map(|v| *v as f64);
-            let kernel = mask.slice(mask_slice);
-            let gradient = (&kernel * &view).sum() / 2.0;
-
-            println!(
-                "{:?} in:\n{}\n * \n{}\n ↓ \n{}\n",
-                v, view, kernel, gradient
-            );
+        let curvature = apply(&heights, &laplacian(), Boundary::Clamp);
+        assert_eq!(curvature.dim(), heights.dim());
+        // 8 + 6 + 8 + 6 - 4 × 5
+        assert_eq!(curvature[(2, 2)], 8);
+        // with clamped edges a low point is lower than everything around it
+        for index in low_points(&heights).coordinates {
+            assert!(curvature[index] > 0);
         }
+        assert_eq!(apply(&heights, &laplacian(), Boundary::Skip).dim(), (3, 8));
+
+        let (down, across) = gradients();
+        let ramp = arr2(&[[0, 1, 2, 3], [2, 3, 4, 5]]);
+        assert_eq!(
+            apply(&ramp, &across, Boundary::Clamp),
+            arr2(&[[1, 2, 2, 1], [1, 2, 2, 1]])
+        );
+        assert_eq!(
+            apply(&ramp, &down, Boundary::Clamp),
+            arr2(&[[2, 2, 2, 2], [2, 2, 2, 2]])
+        );
     }
 }
 
 mod input {
     use ndarray::Array2;
+    use std::fmt;
     use std::fs::File;
     use std::io::{self, prelude::*};
     use std::path::Path;
@@ -520,21 +1132,84 @@ mod input {
         Ok(buffer)
     }
 
-    pub fn read_char_array(s: &str) -> Array2<i32> {
-        let n_cols = s.lines().next().unwrap().len();
-        let n_rows = s.lines().count();
+    /// Why text could not be read as a grid. Lines and columns count from 1.
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum GridError {
+        Empty,
+        Ragged {
+            line: usize,
+            width: usize,
+            expected: usize,
+        },
+        Unmapped {
+            line: usize,
+            column: usize,
+            ch: char,
+        },
+    }
 
-        let mut array = Array2::<i32>::zeros((n_rows, n_cols));
-        s.lines()
-            .flat_map(|line| line.chars())
-            .collect::<String>()
-            .chars()
-            .zip(array.iter_mut())
-            .for_each(|(ch, e)| {
-                if let '0'..='9' = ch {
-                    *e = ch.to_digit(10).unwrap() as i32
+    impl fmt::Display for GridError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                GridError::Empty => write!(f, "no lines to read a grid from"),
+                GridError::Ragged {
+                    line,
+                    width,
+                    expected,
+                } => write!(
+                    f,
+                    "line {} has {} characters, expected {}",
+                    line, width, expected
+                ),
+                GridError::Unmapped { line, column, ch } => {
+                    write!(f, "unexpected {:?} at line {}, column {}", ch, line, column)
                 }
-            });
-        array
+            }
+        }
+    }
+
+    /// Read a rectangular grid with one cell per character, turning each
+    /// character into a cell with `map`, which gives `None` for characters
+    /// that do not belong in the grid.
+    pub fn read_grid<T, F>(s: &str, map: F) -> Result<Array2<T>, GridError>
+    where
+        F: Fn(char) -> Option<T>,
+    {
+        let mut cells = Vec::new();
+        let (mut n_rows, mut n_cols) = (0, 0);
+        for (row, line) in s.lines().enumerate() {
+            let width = line.chars().count();
+            if row == 0 {
+                n_cols = width;
+            } else if width != n_cols {
+                return Err(GridError::Ragged {
+                    line: row + 1,
+                    width,
+                    expected: n_cols,
+                });
+            }
+            for (column, ch) in line.chars().enumerate() {
+                let cell = map(ch).ok_or(GridError::Unmapped {
+                    line: row + 1,
+                    column: column + 1,
+                    ch,
+                })?;
+                cells.push(cell);
+            }
+            n_rows += 1;
+        }
+        if n_rows == 0 {
+            return Err(GridError::Empty);
+        }
+        Ok(Array2::from_shape_vec((n_rows, n_cols), cells).expect("every row has n_cols cells"))
+    }
+
+    /// Decimal digits as heights.
+    pub fn digit(ch: char) -> Option<i32> {
+        ch.to_digit(10).map(|d| d as i32)
+    }
+
+    pub fn read_char_array(s: &str) -> Result<Array2<i32>, GridError> {
+        read_grid(s, digit)
     }
 }